@@ -0,0 +1,47 @@
+use crate::ai::neural_network::NeuralNetwork;
+use crate::ai::neural_network_utils::NeuralNetworkOptions;
+
+// Anything that turns the sensor input into one output per direction can drive the snake.
+// The genetic algorithm only sees the flat weights vector.
+pub trait Brain: Sized {
+    fn from_weights(weights: Vec<f64>, options: NeuralNetworkOptions) -> Result<Self, String>;
+
+    fn input_size(&self) -> usize;
+
+    fn output_size(&self) -> usize;
+
+    fn forward(&self, input: &[f64]) -> Vec<f64>;
+
+    fn update_weights(&mut self, weights: Vec<f64>);
+
+    // Weights that had to be changed to fit the brain, e.g. clamped to the maximum absolute weight
+    fn clamped_weights(&self) -> usize {
+        0
+    }
+}
+
+impl Brain for NeuralNetwork {
+    fn from_weights(weights: Vec<f64>, options: NeuralNetworkOptions) -> Result<Self, String> {
+        NeuralNetwork::new_with_weights(weights, options)
+    }
+
+    fn input_size(&self) -> usize {
+        self.get_input_size()
+    }
+
+    fn output_size(&self) -> usize {
+        self.get_output_size()
+    }
+
+    fn forward(&self, input: &[f64]) -> Vec<f64> {
+        self.get_output(input.to_vec()).unwrap()
+    }
+
+    fn update_weights(&mut self, weights: Vec<f64>) {
+        NeuralNetwork::update_weights(self, weights)
+    }
+
+    fn clamped_weights(&self) -> usize {
+        self.get_clamped_weights()
+    }
+}
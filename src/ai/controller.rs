@@ -0,0 +1,347 @@
+use rand::rngs::StdRng;
+use crate::ai::brain::Brain;
+use crate::ai::neural_network::NeuralNetwork;
+use crate::ai::neural_network_utils::output_entropy;
+use crate::ai::snake_trainer::{direction_for_output_index, generate_network_input_with_settings, interpret_network_output,
+                               sample_network_output, validate_network_input, InputHistory, InputSettings, InputSizeMismatch, Move};
+use crate::snake::snake_game::{Ate, Direction, Food, Snake};
+use crate::snake::symmetry::Symmetry;
+
+// Anything that can steer the snake, e.g. a network or a scripted list of moves in tests
+pub trait Controller {
+    fn decide(&mut self, snake: &Snake, food: &Food) -> Direction;
+}
+
+// Takes the move with the shortest path to the food, a baseline to compare the genomes with
+pub struct OracleController;
+
+impl Controller for OracleController {
+    fn decide(&mut self, snake: &Snake, food: &Food) -> Direction {
+        let board = snake.get_board();
+
+        [Move::FORWARD, Move::LEFT, Move::RIGHT].into_iter()
+            .filter(|move_dir| !matches!(snake.would_die(*move_dir, food, &board), Some(Ate::Border | Ate::Itself)))
+            .map(|move_dir| snake.direction_for_move(move_dir))
+            .min_by_key(|direction| {
+                let mut position = snake.get_head_coordinates();
+                position.make_a_move(*direction);
+
+                snake.safe_path_from(position, food, &board).unwrap_or(usize::MAX)
+            })
+            .unwrap_or_else(|| snake.get_current_direction())
+    }
+}
+
+// Keeps the previous output unless the new best one beats it by more than the margin
+pub struct MoveSmoother {
+    margin: f64,
+    last_index: Option<usize>
+}
+
+impl MoveSmoother {
+    pub fn new(margin: f64) -> Self {
+        MoveSmoother {margin, last_index: None}
+    }
+
+    pub fn choose(&mut self, output: &[f64]) -> usize {
+        let best_index = output.iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+            .unwrap_or(0);
+
+        match self.last_index {
+            Some(last_index) if output[best_index] - output[last_index] <= self.margin => last_index,
+            _ => {
+                self.last_index = Some(best_index);
+                best_index
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.last_index = None;
+    }
+}
+
+pub(crate) struct NeuralController<B: Brain = NeuralNetwork> {
+    brain: B,
+    track_entropy: bool,
+    last_entropy: Option<f64>,
+    symmetry: Symmetry,
+    input_settings: InputSettings,
+    move_smoother: Option<MoveSmoother>,
+    sampling_rng: Option<StdRng>,
+    mask_unsafe: bool,
+    input_history: InputHistory
+}
+
+impl<B: Brain> NeuralController<B> {
+    pub fn new(brain: B) -> Self {
+        NeuralController {
+            brain,
+            track_entropy: false,
+            last_entropy: None,
+            symmetry: Symmetry::IDENTITY,
+            input_settings: InputSettings::default(),
+            move_smoother: None,
+            sampling_rng: None,
+            mask_unsafe: false,
+            input_history: InputHistory::new(1)
+        }
+    }
+
+    // The network sees the board transformed by the symmetry, its move is mapped back to the real board
+    pub fn with_symmetry(mut self, symmetry: Symmetry) -> Self {
+        self.symmetry = symmetry;
+        self
+    }
+
+    pub fn with_input_settings(mut self, input_settings: InputSettings) -> Self {
+        self.input_settings = input_settings;
+        self.input_history = InputHistory::new(input_settings.history_frames);
+        self
+    }
+
+    // Only meant for the demo, stops the snake from jittering between nearly tied moves
+    pub fn with_move_smoothing(mut self, margin: f64) -> Self {
+        self.move_smoother = Some(MoveSmoother::new(margin));
+        self
+    }
+
+    // Moves are drawn from the network output instead of taking the best one
+    pub fn with_sampling(mut self, rng: StdRng) -> Self {
+        self.sampling_rng = Some(rng);
+        self
+    }
+
+    // Moves into a wall or the body are only taken when there is no other choice
+    pub fn with_unsafe_move_masking(mut self) -> Self {
+        self.mask_unsafe = true;
+        self
+    }
+
+    pub fn with_entropy_tracking(mut self) -> Self {
+        self.track_entropy = true;
+        self
+    }
+
+    pub fn get_last_entropy(&self) -> Option<f64> {
+        self.last_entropy
+    }
+
+    pub fn get_clamped_weights(&self) -> usize {
+        self.brain.clamped_weights()
+    }
+
+    // Fails early when the sensor layout and the network's first layer disagree
+    pub fn build_input(&self, snake: &Snake, food: &Food) -> Result<Vec<f64>, InputSizeMismatch> {
+        let input = self.input_history.stacked_with(&self.build_frame(snake, food));
+
+        validate_network_input(&input, self.brain.input_size())?;
+
+        Ok(input)
+    }
+
+    fn build_frame(&self, snake: &Snake, food: &Food) -> Vec<f64> {
+        generate_network_input_with_settings(snake, food, self.symmetry, &self.input_settings)
+    }
+
+    // The next game shouldn't remember the previous board
+    pub fn reset_input_history(&mut self) {
+        self.input_history.clear();
+    }
+
+    // The greedy move for this state, without touching the smoother, the sampling rng or the tracked entropy
+    pub fn preview(&self, snake: &Snake, food: &Food) -> Move {
+        let input = self.build_input(snake, food).unwrap_or_else(|error| panic!("{}", error));
+
+        let direction = interpret_network_output(&self.brain.forward(&input)).unwrap();
+
+        snake.move_for_direction(self.symmetry.inverse().transform_direction(direction))
+    }
+
+    // Whether the direction of every output keeps the snake alive, turning back counts as going forward
+    fn safe_outputs(&self, snake: &Snake, food: &Food, n_of_outputs: usize) -> Vec<bool> {
+        let safe_moves = snake.safe_moves(food, &snake.get_board());
+        let inverse = self.symmetry.inverse();
+
+        (0..n_of_outputs)
+            .map(|index| inverse.transform_direction(direction_for_output_index(index)))
+            .map(|direction| safe_moves[snake.move_for_direction(direction) as usize])
+            .collect()
+    }
+
+    // Sampling renormalizes over the safe outputs, anything else must never pick a masked one
+    fn masked_value(&self) -> f64 {
+        if self.sampling_rng.is_some() && self.move_smoother.is_none() {
+            0.0
+        } else {
+            f64::NEG_INFINITY
+        }
+    }
+
+    pub fn update_weights(&mut self, new_weights: Vec<f64>) {
+        self.brain.update_weights(new_weights);
+        self.last_entropy = None;
+        self.input_history.clear();
+
+        if let Some(move_smoother) = &mut self.move_smoother {
+            move_smoother.reset();
+        }
+    }
+}
+
+impl<B: Brain> Controller for NeuralController<B> {
+    fn decide(&mut self, snake: &Snake, food: &Food) -> Direction {
+        let frame = self.build_frame(snake, food);
+        let input = self.input_history.push(frame);
+
+        validate_network_input(&input, self.brain.input_size()).unwrap_or_else(|error| panic!("{}", error));
+
+        let output = self.brain.forward(&input);
+
+        debug_assert_eq!(output.len(), self.brain.output_size(), "Brain output doesn't match its output size");
+
+        if self.track_entropy {
+            self.last_entropy = Some(output_entropy(&output));
+        }
+
+        let output = if self.mask_unsafe {
+            mask_unsafe_output(&output, &self.safe_outputs(snake, food, output.len()), self.masked_value())
+        } else {
+            output
+        };
+
+        let direction = match (&mut self.move_smoother, &mut self.sampling_rng) {
+            (Some(move_smoother), _) => direction_for_output_index(move_smoother.choose(&output)),
+            (None, Some(rng)) => sample_network_output(&output, rng)
+                .or_else(|_| interpret_network_output(&output))
+                .unwrap(),
+            (None, None) => interpret_network_output(&output).unwrap()
+        };
+
+        self.symmetry.inverse().transform_direction(direction)
+    }
+}
+
+// Unsafe outputs are replaced with `masked_value`, the output stays as it is when every move is deadly
+fn mask_unsafe_output(output: &[f64], safe_outputs: &[bool], masked_value: f64) -> Vec<f64> {
+    if !safe_outputs.contains(&true) {
+        return output.to_vec()
+    }
+
+    output.iter()
+        .zip(safe_outputs)
+        .map(|(value, safe)| if *safe { *value } else { masked_value })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ai::brain::Brain;
+    use crate::ai::controller::{Controller, MoveSmoother, NeuralController};
+    use crate::ai::neural_network::NeuralNetwork;
+    use crate::ai::neural_network_utils::{NeuralNetworkOptions, Softmax};
+    use crate::ai::snake_trainer::{generate_network_input, InputSettings, InputSizeMismatch, FIRST_LAYER_SIZE};
+    use crate::snake::snake_game::{Direction, Food, Position, Snake};
+
+    // Always prefers LEFT, then RIGHT, then UP, DOWN is the least likely
+    struct FixedOutputBrain;
+
+    impl Brain for FixedOutputBrain {
+        fn from_weights(_weights: Vec<f64>, _options: NeuralNetworkOptions) -> Result<Self, String> {
+            Ok(FixedOutputBrain)
+        }
+
+        fn input_size(&self) -> usize {
+            FIRST_LAYER_SIZE
+        }
+
+        fn output_size(&self) -> usize {
+            4
+        }
+
+        fn forward(&self, _input: &[f64]) -> Vec<f64> {
+            vec![0.2, 0.3, 0.1, 0.4]
+        }
+
+        fn update_weights(&mut self, _weights: Vec<f64>) {}
+    }
+
+    #[test]
+    pub fn unsafe_move_masking_should_redirect_to_best_safe_move() {
+        //given
+        // Next to the left wall, facing up
+        let snake = Snake::from_positions(Position::new(0, 5), &[Position::new(0, 6)]);
+        let food = Food::new(Position::new(9, 9));
+        let mut controller = NeuralController::new(FixedOutputBrain);
+        let mut masked_controller = NeuralController::new(FixedOutputBrain).with_unsafe_move_masking();
+
+        //when
+        let direction = controller.decide(&snake, &food);
+        let masked_direction = masked_controller.decide(&snake, &food);
+
+        //then
+        assert!(direction == Direction::LEFT, "Without masking the snake should go into the wall");
+        assert!(masked_direction == Direction::RIGHT, "Masking should pick the best of the safe moves");
+    }
+
+    #[test]
+    pub fn build_input_should_return_error_on_mismatched_first_layer() {
+        //given
+        let options = NeuralNetworkOptions::new(vec![20, 4], vec![Box::new(Softmax)]);
+        let controller = NeuralController::new(NeuralNetwork::new(options).unwrap());
+
+        //when
+        let input = controller.build_input(&Snake::new(Position::new(5, 5)), &Food::new(Position::new(1, 1)));
+
+        //then
+        assert_eq!(input, Err(InputSizeMismatch {expected: 20, actual: FIRST_LAYER_SIZE}),
+                   "Mismatch should be reported when building the input");
+    }
+
+    #[test]
+    pub fn input_history_should_stack_two_most_recent_frames() {
+        //given
+        let input_settings = InputSettings {history_frames: 2, ..InputSettings::default()};
+        let options = NeuralNetworkOptions::new(vec![input_settings.input_size() as u16, 4], vec![Box::new(Softmax)]);
+        let mut controller = NeuralController::new(NeuralNetwork::new(options).unwrap()).with_input_settings(input_settings);
+
+        let first_snake = Snake::new(Position::new(5, 5));
+        let second_snake = Snake::from_positions(Position::new(5, 4), &[Position::new(5, 5)]);
+        let food = Food::new(Position::new(1, 1));
+        let (first_frame, second_frame) = (generate_network_input(&first_snake, &food), generate_network_input(&second_snake, &food));
+
+        //when
+        let first_input = controller.build_input(&first_snake, &food).unwrap();
+        controller.decide(&first_snake, &food);
+        let second_input = controller.build_input(&second_snake, &food).unwrap();
+
+        //then
+        assert_eq!(input_settings.input_size(), 2 * FIRST_LAYER_SIZE, "Input should grow with every stacked frame");
+        assert_eq!(first_input, [first_frame.clone(), first_frame.clone()].concat(), "First frame should fill the missing history");
+        assert_eq!(second_input, [second_frame, first_frame].concat(), "Newest frame should come before the previous one");
+    }
+
+    #[test]
+    pub fn move_smoother_should_keep_move_until_margin_is_exceeded() {
+        //given
+        let mut move_smoother = MoveSmoother::new(0.1);
+        let outputs = [
+            [0.30, 0.28, 0.22, 0.20],
+            [0.29, 0.31, 0.20, 0.20],
+            [0.25, 0.34, 0.21, 0.20],
+            [0.20, 0.45, 0.20, 0.15],
+            [0.30, 0.40, 0.15, 0.15]
+        ];
+
+        //when
+        let chosen = outputs.iter()
+            .map(|output| move_smoother.choose(output))
+            .collect::<Vec<_>>();
+
+        //then
+        assert_eq!(chosen, vec![0, 0, 0, 1, 1], "Move should only change once the margin is exceeded");
+    }
+}
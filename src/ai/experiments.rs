@@ -0,0 +1,680 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use image::{GrayImage, Luma};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::ai::controller::{Controller, NeuralController};
+use crate::ai::neural_network::NeuralNetwork;
+use crate::ai::snake_trainer::{play_until_end, MLSnakeOptions, Move, SnakeTrainer, VisionMode};
+use crate::snake::food_spawner::{FoodSpawner, SeededFoodSpawner};
+use crate::snake::game_simulation::{EndReason, GameSimulation};
+use crate::snake::snake_game::{Board, Direction, Food, Position, Snake};
+use crate::visualisation::game_constants::GRID_SIZE;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct GameMetrics {
+    pub score: u32,
+    pub steps: u32,
+    // Longest the snake got, hunger can shrink it again before the end
+    pub max_length: usize,
+    pub end_reason: Option<EndReason>,
+    // Every decision in the game was the same move, e.g. always FORWARD
+    pub constant_policy: bool
+}
+
+impl GameMetrics {
+    pub fn from_simulation(simulation: &GameSimulation, constant_policy: bool) -> Self {
+        GameMetrics {
+            score: simulation.get_score(),
+            steps: simulation.get_steps(),
+            max_length: simulation.get_max_length(),
+            end_reason: simulation.get_end_reason(),
+            constant_policy
+        }
+    }
+
+    // Eating 10 apples in 200 steps is better than 10 apples in 2000 steps
+    pub fn apples_per_1000_steps(&self) -> f64 {
+        if self.steps == 0 {
+            return 0.0
+        }
+
+        1000.0 * self.score as f64 / self.steps as f64
+    }
+}
+
+// One line report of a game, e.g. to print next to the seed it was played on
+impl Display for GameMetrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "score: {}, steps: {}, apples per 1000 steps: {:.2}, end reason: {:?}, constant policy: {}",
+               self.score, self.steps, self.apples_per_1000_steps(), self.end_reason, self.constant_policy)
+    }
+}
+
+// Remembers the first move relative to the snake and whether any later move differed from it
+struct MoveRecorder<'a, C: Controller> {
+    controller: &'a mut C,
+    first_move: Option<Move>,
+    changed_move: bool
+}
+
+impl<C: Controller> Controller for MoveRecorder<'_, C> {
+    fn decide(&mut self, snake: &Snake, food: &Food) -> Direction {
+        let direction = self.controller.decide(snake, food);
+        let move_dir = snake.move_for_direction(direction);
+
+        match self.first_move {
+            Some(first_move) => self.changed_move |= first_move != move_dir,
+            None => self.first_move = Some(move_dir)
+        }
+
+        direction
+    }
+}
+
+pub fn play_and_measure<C: Controller>(controller: &mut C, mut simulation: GameSimulation) -> GameMetrics {
+    let mut move_recorder = MoveRecorder {controller, first_move: None, changed_move: false};
+
+    play_until_end(&mut move_recorder, &mut simulation);
+
+    GameMetrics::from_simulation(&simulation, !move_recorder.changed_move)
+}
+
+// Spawn position and food placements of a fixed game, the seed places food once `foods` run out
+#[derive(Clone, PartialEq, Debug)]
+pub struct Scenario {
+    pub spawn: Position,
+    pub foods: Vec<Position>,
+    pub seed: u64
+}
+
+impl Scenario {
+    pub fn new(spawn: Position, foods: Vec<Position>, seed: u64) -> Self {
+        Scenario {spawn, foods, seed}
+    }
+}
+
+struct ScenarioFoodSpawner {
+    foods: VecDeque<Position>,
+    fallback: SeededFoodSpawner
+}
+
+impl FoodSpawner for ScenarioFoodSpawner {
+    fn spawn(&mut self, snake: &Snake) -> Food {
+        match self.foods.pop_front() {
+            Some(position) if !snake.is_in_position(position) => Food::new(position),
+            _ => self.fallback.spawn(snake)
+        }
+    }
+}
+
+// Corners, walls and the center, fixed so the behavior of a known genome can be pinned down
+pub fn scenario_battery() -> Vec<Scenario> {
+    vec![
+        Scenario::new(Position::new(GRID_SIZE.0 / 2, GRID_SIZE.1 / 2), vec![Position::new(GRID_SIZE.0 / 2 + 3, GRID_SIZE.1 / 2)], 1),
+        Scenario::new(Position::new(2, 2), vec![Position::new(GRID_SIZE.0 - 1, GRID_SIZE.1 - 1)], 2),
+        Scenario::new(Position::new(GRID_SIZE.0 - 3, 1), vec![Position::new(0, GRID_SIZE.1 - 1), Position::new(GRID_SIZE.0 - 1, 0)], 3),
+        Scenario::new(Position::new(3, GRID_SIZE.1 - 2), vec![Position::new(3, 0), Position::new(GRID_SIZE.0 / 2, GRID_SIZE.1 / 2)], 4)
+    ]
+}
+
+pub fn evaluate_battery(weights: &[f64], options: &MLSnakeOptions) -> Result<Vec<GameMetrics>, String> {
+    evaluate_scenarios(weights, options, &scenario_battery())
+}
+
+// Fails on the first scenario that doesn't fit on the board
+pub fn evaluate_scenarios(weights: &[f64], options: &MLSnakeOptions, scenarios: &[Scenario]) -> Result<Vec<GameMetrics>, String> {
+    scenarios.iter()
+        .map(|scenario| {
+            Ok(play_and_measure(&mut controller_for(weights, options), scenario_simulation(scenario)?))
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct VisionReport {
+    pub vision_mode: VisionMode,
+    pub scores: Vec<(u64, u32)>,
+    pub mean_score: f64
+}
+
+// Trains a population for both vision modes and plays the best genomes on the same seeded boards
+pub fn compare_vision_modes(options: &MLSnakeOptions, seeds: &[u64]) -> (VisionReport, VisionReport) {
+    (vision_report(options, VisionMode::Binary, seeds), vision_report(options, VisionMode::Distance, seeds))
+}
+
+fn vision_report(options: &MLSnakeOptions, vision_mode: VisionMode, seeds: &[u64]) -> VisionReport {
+    let options = options.clone().with_vision_mode(vision_mode);
+
+    let (scores, mean_score) = champion_scores(&options, seeds);
+
+    VisionReport {vision_mode, scores, mean_score}
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ArchitectureReport {
+    pub layers_sizes: Vec<u16>,
+    pub layers_functions: Vec<String>,
+    pub scores: Vec<(u64, u32)>,
+    pub mean_score: f64
+}
+
+// Same as `compare_vision_modes`, but for two networks, e.g. Tanh against ReLU hidden layers.
+// Both options should share the population and evaluation seeds so only the architecture differs.
+pub fn compare_architectures(options_a: &MLSnakeOptions, options_b: &MLSnakeOptions, seeds: &[u64]) -> (ArchitectureReport, ArchitectureReport) {
+    (architecture_report(options_a, seeds), architecture_report(options_b, seeds))
+}
+
+fn architecture_report(options: &MLSnakeOptions, seeds: &[u64]) -> ArchitectureReport {
+    let neural_network_options = options.get_neural_network_options();
+
+    let (scores, mean_score) = champion_scores(options, seeds);
+
+    ArchitectureReport {
+        layers_sizes: neural_network_options.layers_sizes_vec.clone(),
+        layers_functions: neural_network_options.layers_functions.iter().map(|function| format!("{:?}", function)).collect(),
+        scores,
+        mean_score
+    }
+}
+
+// Trains a population and plays its best genome once for every seed
+fn champion_scores(options: &MLSnakeOptions, seeds: &[u64]) -> (Vec<(u64, u32)>, f64) {
+    let best_chromosomes = SnakeTrainer::train_headless(options).get_best_chromosomes();
+
+    let scores = seeds.iter()
+        .map(|seed| (*seed, play_seeded_game(&best_chromosomes, options, *seed)))
+        .collect::<Vec<_>>();
+
+    let mean_score = scores.iter()
+        .map(|(_, score)| *score as f64)
+        .sum::<f64>() / scores.len().max(1) as f64;
+
+    (scores, mean_score)
+}
+
+const FITNESS_SLICE_SEED: u64 = 0;
+
+// Varies the weight at `dim` evenly across `range` while the others stay fixed, returns (weight, fitness) pairs
+pub fn fitness_slice(base: &[f64], dim: usize, range: (f64, f64), steps: usize, options: &MLSnakeOptions) -> Vec<(f64, f64)> {
+    let (start, end) = range;
+    let step_size = if steps > 1 { (end - start) / (steps - 1) as f64 } else { 0.0 };
+
+    let mut chromosomes = base.to_vec();
+
+    (0..steps)
+        .map(|i| {
+            let weight = start + step_size * i as f64;
+            chromosomes[dim] = weight;
+
+            (weight, options.evaluate_seeded(&chromosomes, FITNESS_SLICE_SEED))
+        })
+        .collect()
+}
+
+// The seed decides the starting position and every food placement
+pub fn play_seeded_game(chromosomes: &[f64], options: &MLSnakeOptions, seed: u64) -> u32 {
+    run_genome(chromosomes, options, seed).score
+}
+
+// Plays a single seeded game, the caller decides whether to print the metrics
+pub fn run_genome(chromosomes: &[f64], options: &MLSnakeOptions, seed: u64) -> GameMetrics {
+    play_and_measure(&mut controller_for(chromosomes, options), seeded_simulation(seed))
+}
+
+// A game this short without an apple means the genome most likely doesn't fit the network
+const SANITY_CHECK_MAX_STEPS: u32 = 3;
+
+// Spawned in the top right corner facing the wall, a genome that ignores its input dies within a couple of steps
+fn sanity_check_scenario() -> Scenario {
+    Scenario::new(Position::new(GRID_SIZE.0 - 2, 1), vec![Position::new(0, GRID_SIZE.1 - 1)], 0)
+}
+
+// Quick headless game to catch a broken load before the genome is demoed
+pub fn sanity_check_genome(weights: &[f64], options: &MLSnakeOptions) -> Result<GameMetrics, String> {
+    let neural_network = NeuralNetwork::new_with_weights(weights.to_vec(), options.get_neural_network_options().clone())?;
+    let mut controller = NeuralController::new(neural_network).with_input_settings(options.get_input_settings());
+
+    let metrics = play_and_measure(&mut controller, scenario_simulation(&sanity_check_scenario())?);
+
+    if metrics.score == 0 && metrics.steps <= SANITY_CHECK_MAX_STEPS {
+        return Err(format!("Genome died after {} steps without eating, end reason: {:?}", metrics.steps, metrics.end_reason))
+    }
+
+    Ok(metrics)
+}
+
+// Fraction of the seeded games in which the snake got at least `target_length` long
+pub fn achieved_length(weights: &[f64], options: &MLSnakeOptions, seeds: &[u64], target_length: usize) -> f64 {
+    let metrics = seeds.iter()
+        .map(|seed| run_genome(weights, options, *seed))
+        .collect::<Vec<_>>();
+
+    fraction_reaching(&metrics, target_length)
+}
+
+fn fraction_reaching(metrics: &[GameMetrics], target_length: usize) -> f64 {
+    if metrics.is_empty() {
+        return 0.0
+    }
+
+    metrics.iter().filter(|metrics| metrics.max_length >= target_length).count() as f64 / metrics.len() as f64
+}
+
+// Counts how many times the head entered every cell over `games` seeded games, indexed as [y][x]
+pub fn visit_heatmap(weights: &[f64], options: &MLSnakeOptions, games: u64) -> Vec<Vec<u32>> {
+    let board = Board::default();
+    let mut heatmap = vec![vec![0; board.width as usize]; board.height as usize];
+
+    for seed in 0..games {
+        let mut controller = controller_for(weights, options);
+        let mut simulation = seeded_simulation(seed);
+
+        while simulation.get_end_reason().is_none() {
+            let move_dir = controller.decide(simulation.get_snake(), simulation.get_food());
+
+            simulation.step(move_dir);
+
+            let head = simulation.get_snake().get_head_coordinates();
+
+            if board.contains(head) {
+                heatmap[head.y as usize][head.x as usize] += 1;
+            }
+        }
+    }
+
+    heatmap
+}
+
+// The preferred move of the genome with the apple placed on every cell, indexed as [y][x], None on the snake's cells
+pub fn policy_map(weights: &[f64], options: &MLSnakeOptions, snake: &Snake) -> Vec<Vec<Option<Move>>> {
+    let board = Board::default();
+    let controller = controller_for(weights, options);
+
+    (0..board.height)
+        .map(|y| (0..board.width)
+            .map(|x| Position::new(x, y))
+            .map(|position| (!snake.is_in_position(position))
+                .then(|| controller.preview(snake, &Food::new(position))))
+            .collect())
+        .collect()
+}
+
+// Every cell shows where the snake would go with the apple there, the head is H and the body is o
+pub fn render_policy_map(policy_map: &[Vec<Option<Move>>], snake: &Snake) -> String {
+    policy_map.iter()
+        .enumerate()
+        .map(|(y, row)| row.iter()
+            .enumerate()
+            .map(|(x, move_dir)| match move_dir {
+                Some(move_dir) => match snake.direction_for_move(*move_dir) {
+                    Direction::UP => '↑',
+                    Direction::RIGHT => '→',
+                    Direction::DOWN => '↓',
+                    Direction::LEFT => '←'
+                },
+                None if snake.get_head_coordinates() == Position::new(x as i16, y as i16) => 'H',
+                None => 'o'
+            })
+            .collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// The most visited cell is white, cells that were never visited are black
+pub fn save_heatmap_png(heatmap: &[Vec<u32>], path: &Path) -> Result<(), String> {
+    let height = heatmap.len() as u32;
+    let width = heatmap.first().map_or(0, |row| row.len()) as u32;
+    let max_visits = heatmap.iter().flatten().copied().max().unwrap_or(0).max(1);
+
+    let image = GrayImage::from_fn(width, height, |x, y| {
+        Luma([(heatmap[y as usize][x as usize] as f64 / max_visits as f64 * 255.0).round() as u8])
+    });
+
+    image.save(path).map_err(|error| format!("Couldn't save the heatmap to {}: {}", path.display(), error))
+}
+
+fn controller_for(chromosomes: &[f64], options: &MLSnakeOptions) -> NeuralController {
+    let neural_network = NeuralNetwork::new_with_weights(chromosomes.to_vec(),
+                                                         options.get_neural_network_options().clone()).unwrap();
+
+    NeuralController::new(neural_network).with_input_settings(options.get_input_settings())
+}
+
+fn seeded_simulation(seed: u64) -> GameSimulation {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let snake = Snake::new(Position::new(rng.gen_range(2..GRID_SIZE.0 - 2), rng.gen_range(2..GRID_SIZE.1 - 2)));
+
+    let mut food_spawner = SeededFoodSpawner::new(seed);
+    let food = food_spawner.spawn(&snake);
+
+    GameSimulation::with_food_spawner(snake, food, Box::new(food_spawner))
+}
+
+fn scenario_simulation(scenario: &Scenario) -> Result<GameSimulation, String> {
+    let board = Board::default();
+    let snake = Snake::try_new(scenario.spawn, board)?;
+
+    if let Some(position) = scenario.foods.iter().find(|position| !board.contains(**position)) {
+        return Err(format!("Food at ({}, {}) is off the board", position.x, position.y))
+    }
+
+    let mut food_spawner = ScenarioFoodSpawner {
+        foods: scenario.foods.iter().copied().collect(),
+        fallback: SeededFoodSpawner::new(scenario.seed)
+    };
+    let food = food_spawner.spawn(&snake);
+
+    Ok(GameSimulation::with_food_spawner(snake, food, Box::new(food_spawner)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ai::experiments::{achieved_length, compare_architectures, compare_vision_modes, controller_for, fraction_reaching, evaluate_battery, evaluate_scenarios, fitness_slice, sanity_check_genome, scenario_battery,
+                                 play_and_measure, policy_map, render_policy_map, scenario_simulation, seeded_simulation, visit_heatmap, GameMetrics,
+                                 Scenario};
+    use crate::ai::controller::Controller;
+    use crate::snake::snake_game::{Direction, Food, Position, Snake};
+    use crate::visualisation::game_constants::GRID_SIZE;
+    use crate::ai::genetic_algorithm::PopulationOptions;
+    use crate::ai::test_helpers::{single_layer_options, small_options};
+    use crate::ai::neural_network_utils::{Function, GELU, Linear, NeuralNetworkOptions, ReLU, Softmax};
+    use crate::ai::snake_trainer::{play_until_end, MLSnakeOptions, Move, VisionMode, FIRST_LAYER_SIZE};
+
+    // Repeats the moves in order, relative to the snake's current direction
+    struct MoveCycleController {
+        moves: Vec<Move>,
+        index: usize
+    }
+
+    impl Controller for MoveCycleController {
+        fn decide(&mut self, snake: &Snake, _food: &Food) -> Direction {
+            let move_dir = self.moves[self.index % self.moves.len()];
+            self.index += 1;
+
+            snake.direction_for_move(move_dir)
+        }
+    }
+
+    #[test]
+    pub fn play_and_measure_should_flag_constant_policy() {
+        //given
+        let scenario = Scenario::new(Position::new(2, 8), vec![Position::new(0, 0)], 0);
+        let mut forward_controller = MoveCycleController {moves: vec![Move::FORWARD], index: 0};
+        let mut zigzag_controller = MoveCycleController {moves: vec![Move::LEFT, Move::RIGHT], index: 0};
+
+        //when
+        let forward_metrics = play_and_measure(&mut forward_controller, scenario_simulation(&scenario).unwrap());
+        let zigzag_metrics = play_and_measure(&mut zigzag_controller, scenario_simulation(&scenario).unwrap());
+
+        //then
+        assert!(forward_metrics.steps > 1 && zigzag_metrics.steps > 1, "Both games should take a few moves");
+        assert!(forward_metrics.constant_policy, "Always going forward should be flagged");
+        assert!(!zigzag_metrics.constant_policy, "Alternating turns shouldn't be flagged");
+    }
+
+    #[test]
+    pub fn policy_map_should_go_forward_with_apple_straight_ahead() {
+        //given
+        // Single layer that goes towards the apple ray: top, right, bottom and left apple sensors
+        let population_options = PopulationOptions::new(10, FIRST_LAYER_SIZE * 4, -1.0, 1.0, 0.9, 0.3, 0.3, 1).unwrap();
+        let options = MLSnakeOptions::new(population_options,
+                                          single_layer_options());
+        let mut weights = vec![0.0; FIRST_LAYER_SIZE * 4];
+        for (output, apple_sensor) in [1, 4, 7, 10].into_iter().enumerate() {
+            weights[output * FIRST_LAYER_SIZE + apple_sensor] = 5.0;
+        }
+        let snake = Snake::new(Position::new(5, 5));
+
+        //when
+        let policy_map = policy_map(&weights, &options, &snake);
+
+        //then
+        assert_eq!(policy_map.len(), GRID_SIZE.1 as usize);
+        assert!(policy_map.iter().all(|row| row.len() == GRID_SIZE.0 as usize), "There should be a row of cells for every y");
+        assert_eq!(policy_map.iter().flatten().filter(|move_dir| move_dir.is_some()).count(),
+                   (GRID_SIZE.0 * GRID_SIZE.1) as usize - snake.get_length(), "Every free cell should have an arrow");
+
+        assert!(policy_map[5][6..].iter().all(|move_dir| *move_dir == Some(Move::FORWARD)),
+                "Apple straight ahead should be followed");
+
+        let rendered = render_policy_map(&policy_map, &snake);
+        assert_eq!(rendered.lines().nth(5).unwrap().chars().skip(4).collect::<String>(), "oH→→→→");
+    }
+
+    #[test]
+    pub fn apples_per_1000_steps_should_match_scripted_game() {
+        //given
+        // Eats at (6, 5) and (7, 5), then the last food is far away at (0, 0)
+        let scenario = Scenario::new(Position::new(5, 5), vec![Position::new(6, 5), Position::new(7, 5), Position::new(0, 0)], 0);
+        let mut simulation = scenario_simulation(&scenario).unwrap();
+
+        for _ in 0..4 {
+            simulation.step(Direction::RIGHT);
+        }
+
+        //when
+        let metrics = GameMetrics::from_simulation(&simulation, false);
+
+        //then
+        assert_eq!((metrics.score, metrics.steps), (2, 4));
+        assert_eq!(metrics.apples_per_1000_steps(), 1000.0 * 2.0 / 4.0, "Efficiency should be 1000 * score / steps");
+        assert!(metrics.to_string().contains("apples per 1000 steps: 500.00"), "Report should show the efficiency: {}", metrics);
+        assert_eq!(GameMetrics {score: 0, steps: 0, max_length: 2, end_reason: None, constant_policy: false}.apples_per_1000_steps(), 0.0,
+                   "Game without steps should have no efficiency");
+    }
+
+    #[test]
+    pub fn evaluate_scenarios_should_be_stable_across_runs() {
+        //given
+        let options = small_options(2);
+        let weights: Vec<f64> = (0..FIRST_LAYER_SIZE * 4 + 4 * 4).map(|i| (i as f64 * 0.7).cos()).collect();
+        let scenarios = vec![
+            Scenario::new(Position::new(5, 5), vec![Position::new(8, 5), Position::new(8, 9)], 7),
+            Scenario::new(Position::new(9, 2), vec![Position::new(0, 0)], 11)
+        ];
+
+        //when
+        let first_run = evaluate_scenarios(&weights, &options, &scenarios).unwrap();
+        let second_run = evaluate_scenarios(&weights, &options, &scenarios).unwrap();
+
+        //then
+        assert_eq!(first_run.len(), 2, "There should be metrics for every scenario");
+        assert!(first_run.iter().all(|metrics| metrics.end_reason.is_some()), "Every scenario should be played to the end");
+        assert_eq!(first_run, second_run, "Metrics of the same genome shouldn't change between runs");
+        assert_eq!(evaluate_battery(&weights, &options).map(|metrics| metrics.len()), Ok(scenario_battery().len()));
+    }
+
+    #[test]
+    pub fn evaluate_scenarios_should_reject_scenarios_off_the_board() {
+        //given
+        let options = small_options(2);
+        let weights: Vec<f64> = (0..FIRST_LAYER_SIZE * 4 + 4 * 4).map(|i| (i as f64 * 0.7).cos()).collect();
+        let off_board_spawn = Scenario::new(Position::new(GRID_SIZE.0, 2), vec![Position::new(0, 0)], 11);
+        let off_board_body = Scenario::new(Position::new(0, 2), vec![Position::new(5, 5)], 11);
+        let off_board_food = Scenario::new(Position::new(5, 5), vec![Position::new(-1, 5)], 11);
+
+        //when-then
+        for scenario in [off_board_spawn, off_board_body, off_board_food] {
+            assert!(evaluate_scenarios(&weights, &options, std::slice::from_ref(&scenario)).is_err(),
+                    "Scenario {:?} shouldn't be played", scenario);
+        }
+    }
+
+    #[test]
+    pub fn fitness_slice_should_span_range_with_steps_points() {
+        //given
+        let options = small_options(2);
+        let base: Vec<f64> = (0..FIRST_LAYER_SIZE * 4 + 4 * 4).map(|i| (i as f64).sin()).collect();
+
+        //when
+        let slice = fitness_slice(&base, 3, (-2.0, 2.0), 5, &options);
+
+        //then
+        let weights: Vec<f64> = slice.iter().map(|(weight, _)| *weight).collect();
+
+        assert_eq!(weights, vec![-2.0, -1.0, 0.0, 1.0, 2.0], "Weights should be spread evenly over the range");
+    }
+
+    #[test]
+    pub fn fitness_slice_should_be_flat_for_irrelevant_weight() {
+        //given
+        let options = small_options(2);
+        let mut base: Vec<f64> = (0..FIRST_LAYER_SIZE * 4 + 4 * 4).map(|i| (i as f64).sin()).collect();
+
+        // The first hidden neuron doesn't reach the output, so its input weights don't matter
+        for output_neuron in 0..4 {
+            base[FIRST_LAYER_SIZE * 4 + output_neuron * 4] = 0.0;
+        }
+
+        //when
+        let slice = fitness_slice(&base, 0, (-5.0, 5.0), 7, &options);
+
+        //then
+        let first_fitness = slice[0].1;
+
+        assert_eq!(slice.len(), 7);
+        assert!(slice.iter().all(|(_, fitness)| (fitness - first_fitness).abs() < 1e-9),
+                "Fitness should stay the same when varying an irrelevant weight: {:?}", slice);
+    }
+
+    #[test]
+    pub fn visit_heatmap_should_count_every_step_on_board() {
+        //given
+        let options = small_options(2);
+        let weights = vec![0.5; FIRST_LAYER_SIZE * 4 + 4 * 4];
+        let games = 5;
+
+        let total_steps: u32 = (0..games)
+            .map(|seed| {
+                let mut simulation = seeded_simulation(seed);
+                play_until_end(&mut controller_for(&weights, &options), &mut simulation);
+                simulation.get_steps()
+            })
+            .sum();
+
+        //when
+        let heatmap = visit_heatmap(&weights, &options, games);
+
+        //then
+        let visits: u32 = heatmap.iter().flatten().sum();
+
+        // The step into a wall isn't on the board
+        assert!(visits <= total_steps && visits + games as u32 >= total_steps,
+                "Visits: {visits} should match total steps: {total_steps}");
+    }
+
+    #[test]
+    pub fn compare_vision_modes_should_report_every_seed() {
+        //given
+        let options = small_options(2);
+        let seeds = [1, 2, 3];
+
+        //when
+        let (binary_report, distance_report) = compare_vision_modes(&options, &seeds);
+
+        //then
+        assert_eq!(binary_report.vision_mode, VisionMode::Binary, "First report should be for binary vision");
+        assert_eq!(distance_report.vision_mode, VisionMode::Distance, "Second report should be for distance vision");
+
+        for report in [binary_report, distance_report] {
+            assert_eq!(report.scores.iter().map(|(seed, _)| *seed).collect::<Vec<_>>(), seeds.to_vec(),
+                       "There should be a score for every seed");
+            assert!(report.mean_score >= 0.0, "Mean score shouldn't be negative");
+        }
+    }
+
+    #[test]
+    pub fn compare_architectures_should_match_for_identical_networks() {
+        //given
+        let seeded_options = |hidden_function: Box<dyn Function>| {
+            let population_options = PopulationOptions::new(10, FIRST_LAYER_SIZE * 4 + 4 * 4, -1.0, 1.0, 0.9, 0.3, 0.3, 2)
+                .unwrap()
+                .with_seed(7);
+            let neural_network_options = NeuralNetworkOptions::new(
+                vec![FIRST_LAYER_SIZE as u16, 4, 4],
+                vec![hidden_function, Box::new(Softmax)]
+            );
+
+            MLSnakeOptions::new(population_options, neural_network_options)
+                .with_evaluation_seed(7)
+                .with_evaluation_threads(1)
+        };
+        let seeds = [1, 2, 3];
+
+        //when
+        let (relu_report, gelu_report) = compare_architectures(&seeded_options(Box::new(ReLU)), &seeded_options(Box::new(GELU)), &seeds);
+        let (report_a, report_b) = compare_architectures(&seeded_options(Box::new(ReLU)), &seeded_options(Box::new(ReLU)), &seeds);
+
+        //then
+        assert_eq!(relu_report.layers_functions, vec!["ReLU", "Softmax"], "First report should describe the first network");
+        assert_eq!(gelu_report.layers_functions, vec!["GELU", "Softmax"], "Second report should describe the second network");
+
+        for report in [&relu_report, &gelu_report] {
+            assert_eq!(report.scores.iter().map(|(seed, _)| *seed).collect::<Vec<_>>(), seeds.to_vec(),
+                       "There should be a score for every seed");
+        }
+
+        assert_eq!(report_a, report_b, "Identical networks should get identical reports under the same seeds");
+        assert_eq!(relu_report, report_a, "Training should be repeatable under the same seeds");
+    }
+
+    #[test]
+    pub fn fraction_reaching_should_count_games_with_target_length() {
+        //given
+        // Two apples straight ahead, the third one comes from the seed
+        let metrics: Vec<_> = (0..5)
+            .map(|seed| Scenario::new(Position::new(2, 5), vec![Position::new(4, 5), Position::new(6, 5)], seed))
+            .map(|scenario| {
+                let mut forward_controller = MoveCycleController {moves: vec![Move::FORWARD], index: 0};
+
+                play_and_measure(&mut forward_controller, scenario_simulation(&scenario).unwrap())
+            })
+            .collect();
+
+        //when-then
+        assert_eq!(fraction_reaching(&metrics, 4), 1.0, "Snake should grow to 4 in every game");
+        assert_eq!(fraction_reaching(&metrics, 100), 0.0, "Snake can't grow to 100 on the way to the wall");
+        assert_eq!(fraction_reaching(&[], 4), 0.0, "No games should reach nothing");
+    }
+
+    #[test]
+    pub fn achieved_length_should_be_a_fraction_of_seeds() {
+        //given
+        let options = small_options(2);
+        let weights = vec![0.5; FIRST_LAYER_SIZE * 4 + 4 * 4];
+
+        //when
+        let starting_length = achieved_length(&weights, &options, &[1, 2, 3], 2);
+        let fraction = achieved_length(&weights, &options, &[1, 2, 3], 3);
+
+        //then
+        assert_eq!(starting_length, 1.0, "Every snake starts with length 2");
+        assert!((0.0..=1.0).contains(&fraction), "Fraction should be between 0 and 1, got {fraction}");
+    }
+
+    #[test]
+    pub fn sanity_check_genome_should_reject_genome_that_dies_at_once() {
+        //given
+        let population_options = PopulationOptions::new(10, FIRST_LAYER_SIZE * 4, -1.0, 1.0, 0.9, 0.3, 0.3, 2).unwrap();
+        let options = MLSnakeOptions::new(population_options,
+                                          NeuralNetworkOptions::new(vec![FIRST_LAYER_SIZE as u16, 4], vec![Box::new(Linear)]));
+
+        // Goes DOWN while facing right or down, away from the top wall
+        let mut steering_weights = vec![0.0; FIRST_LAYER_SIZE * 4];
+        steering_weights[2 * FIRST_LAYER_SIZE + 25] = 1.0;
+        steering_weights[2 * FIRST_LAYER_SIZE + 26] = 1.0;
+
+        //when
+        let steering = sanity_check_genome(&steering_weights, &options);
+        let zeroed = sanity_check_genome(&vec![0.0; FIRST_LAYER_SIZE * 4], &options);
+        let wrong_length = sanity_check_genome(&vec![0.0; FIRST_LAYER_SIZE * 3], &options);
+
+        //then
+        assert!(steering.is_ok_and(|metrics| metrics.steps > 3), "Genome that steers away from the wall should pass");
+        assert!(zeroed.is_err(), "Zeroed genome should run into the wall and fail");
+        assert!(wrong_length.is_err(), "Genome of the wrong length should fail");
+    }
+}
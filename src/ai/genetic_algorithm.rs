@@ -1,37 +1,135 @@
-use rand::{Rng, thread_rng};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use itertools::Itertools;
 use rayon::prelude::*;
 use rand_distr::{Normal, Distribution};
 
+// Fitness drives the selection, the apples eaten are only kept to show how well the genome plays
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Evaluation {
+    pub fitness: f64,
+    pub apples: u32,
+    // Game the genome was scored on, the population fills it in so the game can be played again
+    pub key: EvaluationKey,
+    // Genes the evaluated network had to clamp to its maximum absolute weight
+    pub clamped_weights: usize
+}
+
+impl From<f64> for Evaluation {
+    fn from(fitness: f64) -> Self {
+        Evaluation {fitness, ..Default::default()}
+    }
+}
+
+// Which individual of which generation is evaluated, the evaluation can derive its randomness from it
+// so a seeded run doesn't depend on the thread that picks the individual up
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct EvaluationKey {
+    pub generation: u64,
+    pub index: usize
+}
+
 #[derive(Clone, PartialEq)]
 struct Individual {
     chromosomes: Vec<f64>,
-    evaluation: f64
+    evaluation: f64,
+    apples: u32,
+    key: EvaluationKey,
+    clamped_weights: usize,
+    // False once the chromosomes changed since the last evaluation, unchanged individuals keep their cached evaluation
+    evaluated: bool
 }
 
 pub(crate) struct Population {
     individuals: Vec<Individual>,
+    gen_min_val: f64,
+    gen_max_val: f64,
     crossing_prob: f64,
     mutation_prob: f64,
     mutation_range: f64,
     n_of_generations: u64,
+    generation: u64,
+    previous_best_score: f64,
+    diversity_stop_threshold: Option<f64>,
+    rng: StdRng
+}
+
+#[derive(Clone, Debug)]
+pub struct GenerationStats {
+    pub generation: u64,
+    pub best_score: f64,
+    pub mean_score: f64,
+    // Change of the best score since the previous generation, negative when the best got worse
+    pub best_delta: f64,
+    // Apples eaten by the best genome in its evaluated game
+    pub best_apples: u32,
+    // Game the best genome was evaluated on, replaying it gives back `best_apples`
+    pub best_key: EvaluationKey,
+    // Weights clamped in the networks of the whole population
+    pub clamped_weights: usize,
+    pub best_chromosomes: Vec<f64>
 }
 
+pub(crate) struct Evolution<'a, F, T> {
+    population: &'a mut Population,
+    evaluation_function: F,
+    args: &'a T
+}
+
+impl<F, T, E> Iterator for Evolution<'_, F, T>
+    where
+        F: Fn(&Vec<f64>, &T, EvaluationKey) -> E + Sync + Copy,
+        T: Sync,
+        E: Into<Evaluation> {
+    type Item = GenerationStats;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.population.has_converged() {
+            println!("Diversity: {} fell below the threshold, stopping at generation {}", self.population.diversity(),
+                     self.population.generation);
+            return None
+        }
+
+        self.population.generate_new_population(self.evaluation_function, self.args);
+
+        Some(self.population.get_stats())
+    }
+}
+
+#[derive(Clone)]
 pub struct PopulationOptions {
-    population_size: usize,
-    number_of_chromosomes: usize,
+    pub(crate) population_size: usize,
+    pub(crate) number_of_chromosomes: usize,
     gen_min_val: f64,
     gen_max_val: f64,
     crossing_prob: f64,
     mutation_prob: f64,
     mutation_range: f64,
-    pub(crate) n_of_generations: u64
+    pub(crate) n_of_generations: u64,
+    diversity_stop_threshold: Option<f64>,
+    seed: Option<u64>
 }
 
 impl PopulationOptions {
     pub fn new(population_size: usize, number_of_chromosomes: usize, gen_min_val: f64, gen_max_val: f64,
-               crossing_prob: f64, mutation_prob: f64, mutation_range: f64, n_of_generations: u64) -> Self {
-        PopulationOptions {
+               crossing_prob: f64, mutation_prob: f64, mutation_range: f64, n_of_generations: u64) -> Result<Self, String> {
+        if population_size == 0 {
+            return Err("Population size must be greater than 0".to_string())
+        }
+
+        if !(0.0..=1.0).contains(&crossing_prob) {
+            return Err(format!("Crossing probability: {} must be in range [0, 1]", crossing_prob))
+        }
+
+        if !(0.0..=1.0).contains(&mutation_prob) {
+            return Err(format!("Mutation probability: {} must be in range [0, 1]", mutation_prob))
+        }
+
+        if mutation_range < 0.0 {
+            return Err(format!("Mutation range: {} must not be negative", mutation_range))
+        }
+
+        Ok(PopulationOptions {
             population_size,
             number_of_chromosomes,
             gen_min_val,
@@ -39,27 +137,46 @@ impl PopulationOptions {
             crossing_prob,
             mutation_prob,
             mutation_range,
-            n_of_generations
-        }
+            n_of_generations,
+            diversity_stop_threshold: None,
+            seed: None
+        })
     }
+
+    // Evolution stops once `Population::diversity` drops below the threshold
+    pub fn with_diversity_stop_threshold(mut self, diversity_stop_threshold: f64) -> Self {
+        self.diversity_stop_threshold = Some(diversity_stop_threshold);
+        self
+    }
+
+    // Selection, crossing and mutation draw from an rng with this seed, without it the rng is seeded from entropy
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+// Everything needed to continue the evolution exactly where it stopped, including the rng state
+#[derive(Clone)]
+pub struct PopulationCheckpoint {
+    individuals: Vec<Individual>,
+    generation: u64,
+    previous_best_score: f64,
+    rng: StdRng
 }
 
 impl Individual {
-    fn new(number_of_chromosomes: usize, min_val: f64, max_val: f64) -> Self {
+    fn new<R: Rng>(number_of_chromosomes: usize, min_val: f64, max_val: f64, rng: &mut R) -> Self {
         let mut chromosomes = Vec::with_capacity(number_of_chromosomes);
 
-        let mut rng = thread_rng();
-
         for _ in 0..number_of_chromosomes {
             chromosomes.push(rng.gen_range(min_val..max_val));
         }
 
-        Individual {chromosomes, evaluation: 0.0}
+        Individual {chromosomes, evaluation: 0.0, apples: 0, key: EvaluationKey::default(), clamped_weights: 0, evaluated: false}
     }
 
-    fn cross(mut self, mut other: Self) -> (Self, Self) {
-        let mut rng = thread_rng();
-
+    fn cross<R: Rng>(mut self, mut other: Self, rng: &mut R) -> (Self, Self) {
         let point = rng.gen_range(1..(self.chromosomes.len()-1));
 
         let mut new_chromosomes_1 = Vec::with_capacity(self.chromosomes.len());
@@ -75,35 +192,58 @@ impl Individual {
         new_chromosomes_2.append(remaining_self);
 
         return (
-            Individual {chromosomes: new_chromosomes_1, evaluation: 0.0},
-            Individual {chromosomes: new_chromosomes_2, evaluation: 0.0}
+            Individual {chromosomes: new_chromosomes_1, evaluation: 0.0, apples: 0, key: EvaluationKey::default(), clamped_weights: 0, evaluated: false},
+            Individual {chromosomes: new_chromosomes_2, evaluation: 0.0, apples: 0, key: EvaluationKey::default(), clamped_weights: 0, evaluated: false}
         )
     }
 
-    fn mutate(&mut self, mutation_range: &f64, mutation_prob: &f64) {
-        let mut rng = thread_rng();
-
+    fn mutate<R: Rng>(&mut self, mutation_range: &f64, mutation_prob: &f64, rng: &mut R) {
         self.chromosomes.iter_mut()
             .for_each(|item| {
                 if rng.gen_range(0.0..=1.0) < *mutation_prob {
+                    self.evaluated = false;
                     let mut normal = Normal::new(0.0, *mutation_range)
                         .unwrap_or_else(|_| panic!("Bad variance: item: {}", *item));
-                    *item += normal.sample(&mut rng) * *item;
+                    *item += normal.sample(rng) * *item;
                 }
             })
     }
 
-    fn evaluate<F, T>(&mut self, func: &F, args: &T)
+    // Replaces NaN and infinite genes with random values from the initial range, returns how many were replaced
+    fn repair_non_finite<R: Rng>(&mut self, min_val: f64, max_val: f64, rng: &mut R) -> usize {
+        let mut repaired = 0;
+
+        for chromosome in self.chromosomes.iter_mut().filter(|chromosome| !chromosome.is_finite()) {
+            *chromosome = rng.gen_range(min_val..max_val);
+            repaired += 1;
+        }
+
+        if repaired > 0 {
+            self.evaluated = false;
+        }
+
+        repaired
+    }
+
+    fn evaluate<F, T, E>(&mut self, func: &F, args: &T, key: EvaluationKey)
         where
-            F: Fn(&Vec<f64>, &T) -> f64 {
-        self.evaluation = func(&self.chromosomes, args);
+            F: Fn(&Vec<f64>, &T, EvaluationKey) -> E,
+            E: Into<Evaluation> {
+        let evaluation = func(&self.chromosomes, args, key).into();
+
+        self.evaluation = evaluation.fitness;
+        self.apples = evaluation.apples;
+        self.key = key;
+        self.clamped_weights = evaluation.clamped_weights;
+        self.evaluated = true;
     }
 }
 
 impl Population {
-    pub fn new<F, T>(population_options: PopulationOptions, evaluation_function: F, args: &T) -> Self
+    pub fn new<F, T, E>(population_options: PopulationOptions, evaluation_function: F, args: &T) -> Self
         where
-            F: Fn(&Vec<f64>, &T) -> f64 {
+            F: Fn(&Vec<f64>, &T, EvaluationKey) -> E,
+            E: Into<Evaluation> {
         let population_size = population_options.population_size;
         let number_of_chromosomes = population_options.number_of_chromosomes;
         let gen_min_val = population_options.gen_min_val;
@@ -112,33 +252,98 @@ impl Population {
         let mutation_prob = population_options.mutation_prob;
         let mutation_range = population_options.mutation_range;
         let n_of_generations = population_options.n_of_generations;
+        let diversity_stop_threshold = population_options.diversity_stop_threshold;
+        let mut rng = population_options.seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
 
         let mut individuals = Vec::with_capacity(population_size);
 
-        for _ in 0..population_size {
-            let mut individual = Individual::new(number_of_chromosomes, gen_min_val, gen_max_val);
-            individual.evaluate(&evaluation_function, args);
+        for index in 0..population_size {
+            let mut individual = Individual::new(number_of_chromosomes, gen_min_val, gen_max_val, &mut rng);
+            individual.evaluate(&evaluation_function, args, EvaluationKey {generation: 0, index});
             individuals.push(individual);
         }
 
-        Population {individuals, crossing_prob, mutation_prob, mutation_range, n_of_generations}
+        let mut population = Population {individuals, gen_min_val, gen_max_val, crossing_prob, mutation_prob, mutation_range,
+            n_of_generations, generation: 0, previous_best_score: 0.0, diversity_stop_threshold, rng};
+
+        population.previous_best_score = population.get_best_score();
+
+        population
+    }
+
+    pub fn checkpoint(&self) -> PopulationCheckpoint {
+        PopulationCheckpoint {
+            individuals: self.individuals.clone(),
+            generation: self.generation,
+            previous_best_score: self.previous_best_score,
+            rng: self.rng.clone()
+        }
+    }
+
+    // Continuing from a checkpoint gives the same generations as a run that was never stopped
+    pub fn from_checkpoint(population_options: PopulationOptions, checkpoint: PopulationCheckpoint) -> Self {
+        Population {
+            individuals: checkpoint.individuals,
+            gen_min_val: population_options.gen_min_val,
+            gen_max_val: population_options.gen_max_val,
+            crossing_prob: population_options.crossing_prob,
+            mutation_prob: population_options.mutation_prob,
+            mutation_range: population_options.mutation_range,
+            n_of_generations: population_options.n_of_generations,
+            generation: checkpoint.generation,
+            previous_best_score: checkpoint.previous_best_score,
+            diversity_stop_threshold: population_options.diversity_stop_threshold,
+            rng: checkpoint.rng
+        }
+    }
+
+    // Endless stream of generations, use `take` to limit it
+    pub fn evolve<'a, F, T, E>(&'a mut self, evaluation_function: F, args: &'a T) -> Evolution<'a, F, T>
+        where
+            F: Fn(&Vec<f64>, &T, EvaluationKey) -> E + Sync + Copy,
+            T: Sync,
+            E: Into<Evaluation> {
+        Evolution {population: self, evaluation_function, args}
+    }
+
+    // Evolves the population further from where it stopped
+    pub fn continue_for<F, T, E>(&mut self, extra_generations: u64, evaluation_function: F, args: &T) -> Vec<GenerationStats>
+        where
+            F: Fn(&Vec<f64>, &T, EvaluationKey) -> E + Sync + Copy,
+            T: Sync,
+            E: Into<Evaluation> {
+        self.evolve(evaluation_function, args).take(extra_generations as usize).collect()
     }
 
-    pub fn generate_new_population<F, T>(&mut self, evaluation_function: F, args: &T)
+    pub fn generate_new_population<F, T, E>(&mut self, evaluation_function: F, args: &T)
         where
-            F: Fn(&Vec<f64>, &T) -> f64 + Sync,
-            T: Sync {
+            F: Fn(&Vec<f64>, &T, EvaluationKey) -> E + Sync,
+            T: Sync,
+            E: Into<Evaluation> {
         let new_population = self.selection();
 
         let mut new_population = self.cross_population(new_population);
 
         new_population.iter_mut()
-            .for_each(|individual| individual.mutate(&self.mutation_range, &self.mutation_prob));
+            .for_each(|individual| individual.mutate(&self.mutation_range, &self.mutation_prob, &mut self.rng));
+
+        let repaired: usize = new_population.iter_mut()
+            .map(|individual| individual.repair_non_finite(self.gen_min_val, self.gen_max_val, &mut self.rng))
+            .sum();
+
+        if repaired > 0 {
+            println!("Warning: replaced {} non finite genes in generation {}", repaired, self.generation + 1);
+        }
 
         self.individuals = new_population;
+        self.generation += 1;
+
+        let generation = self.generation;
 
         self.individuals.par_iter_mut()
-            .for_each(|individual| individual.evaluate(&evaluation_function, args));
+            .enumerate()
+            .filter(|(_, individual)| !individual.evaluated)
+            .for_each(|(index, individual)| individual.evaluate(&evaluation_function, args, EvaluationKey {generation, index}));
     }
 
     fn selection(&mut self) -> Vec<Individual> {
@@ -150,7 +355,7 @@ impl Population {
             let mut new_population = self.individuals.clone();
 
             new_population.iter_mut()
-                .for_each(|individual| individual.mutate(&self.mutation_range, &self.mutation_prob));
+                .for_each(|individual| individual.mutate(&self.mutation_range, &self.mutation_prob, &mut self.rng));
 
             return new_population;
         }
@@ -168,37 +373,23 @@ impl Population {
             accumulated_probabilities.push(sum);
         }
 
-        let mut rng = thread_rng();
-
         let mut new_population = Vec::with_capacity(self.individuals.len());
 
         for _ in 0..self.individuals.len() {
-            let r: f64 = rng.gen_range(0.0..=1.0);
-            let mut index = 0;
+            let r: f64 = self.rng.gen_range(0.0..=1.0);
 
-            while index < self.individuals.len() && accumulated_probabilities[index] < r {
-                index += 1;
-            }
-
-            if index < self.individuals.len() {
-                new_population.push(self.individuals[index].clone());
-            } else {
-                panic!("This shouldn't happen. r: {}, accumulated_prob: {:?}",
-                               r, accumulated_probabilities);
-            }
+            new_population.push(self.individuals[select_index(&accumulated_probabilities, r)].clone());
         }
 
         new_population
     }
 
-    fn cross_population(&self, population: Vec<Individual>) -> Vec<Individual> {
-        let mut rng = thread_rng();
-
+    fn cross_population(&mut self, population: Vec<Individual>) -> Vec<Individual> {
         let mut individuals_to_cross = Vec::with_capacity(population.len());
         let mut individuals_not_to_cross = Vec::with_capacity(population.len());
 
         for index in 0..population.len() {
-            if rng.gen_range(0.0..=1.0) < self.crossing_prob {
+            if self.rng.gen_range(0.0..=1.0) < self.crossing_prob {
                 individuals_to_cross.push(population[index].clone());
             } else {
                 individuals_not_to_cross.push(population[index].clone());
@@ -211,7 +402,7 @@ impl Population {
 
         let mut crossed_individuals: Vec<Individual> = individuals_to_cross.into_iter()
             .tuples()
-            .map(|(first, second)| first.cross(second))
+            .map(|(first, second)| first.cross(second, &mut self.rng))
             .flat_map(|(first, second)| vec![first, second])
             .collect();
 
@@ -227,11 +418,476 @@ impl Population {
             .unwrap_or_else(|| panic!("Couldn't find best score: self.individuals.len: {}", self.individuals.len()))
     }
 
-    pub fn get_best_chromosomes(&mut self) -> Vec<f64> {
+    pub fn get_best_chromosomes(&self) -> Vec<f64> {
+        self.peek_best().0.to_vec()
+    }
+
+    // Best genome and its evaluation without touching the individuals' order, on ties the first one wins
+    pub fn peek_best(&self) -> (&[f64], f64) {
+        let best = self.best_individual();
+
+        (&best.chromosomes, best.evaluation)
+    }
+
+    // Apples eaten by the genome `peek_best` returns, unlike its fitness it's meaningful to show to people
+    pub fn get_best_apples(&self) -> u32 {
+        self.best_individual().apples
+    }
+
+    fn best_individual(&self) -> &Individual {
+        self.individuals.iter()
+            .reduce(|best, individual| if individual.evaluation.total_cmp(&best.evaluation).is_gt() { individual } else { best })
+            .unwrap_or_else(|| panic!("Couldn't find best individual"))
+    }
+
+    // Mean standard deviation of every gene across the population, 0 when all individuals are the same
+    pub fn diversity(&self) -> f64 {
+        let n_of_individuals = self.individuals.len() as f64;
+        let n_of_chromosomes = self.individuals.first().map_or(0, |individual| individual.chromosomes.len());
+
+        if n_of_chromosomes == 0 {
+            return 0.0
+        }
+
+        (0..n_of_chromosomes)
+            .map(|gene| {
+                let mean = self.individuals.iter().map(|individual| individual.chromosomes[gene]).sum::<f64>() / n_of_individuals;
+
+                let variance = self.individuals.iter()
+                    .map(|individual| (individual.chromosomes[gene] - mean).powi(2))
+                    .sum::<f64>() / n_of_individuals;
+
+                variance.sqrt()
+            })
+            .sum::<f64>() / n_of_chromosomes as f64
+    }
+
+    // Copies of the `count` best individuals, best first
+    fn best_individuals(&self, count: usize) -> Vec<Individual> {
         self.individuals.iter()
-            .max_by(|a, b| a.evaluation.total_cmp(&b.evaluation))
-            .unwrap_or_else(|| panic!("Couldn't find best individual")).chromosomes.clone()
+            .sorted_by(|a, b| b.evaluation.total_cmp(&a.evaluation))
+            .take(count)
+            .cloned()
+            .collect()
+    }
+
+    // Immigrants take the places of the worst individuals, the order of the others doesn't change
+    fn replace_worst(&mut self, immigrants: Vec<Individual>) {
+        let worst_indices: Vec<_> = (0..self.individuals.len())
+            .sorted_by(|a, b| self.individuals[*a].evaluation.total_cmp(&self.individuals[*b].evaluation))
+            .collect();
+
+        for (index, immigrant) in worst_indices.into_iter().zip(immigrants) {
+            self.individuals[index] = immigrant;
+        }
+    }
+
+    fn has_converged(&self) -> bool {
+        matches!(self.diversity_stop_threshold, Some(threshold) if self.diversity() < threshold)
+    }
+
+    pub fn get_generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn get_stats(&mut self) -> GenerationStats {
+        let mean_score = self.individuals.iter()
+            .map(|individual| individual.evaluation)
+            .sum::<f64>() / self.individuals.len() as f64;
+
+        let best_score = self.get_best_score();
+        let best_delta = best_score - self.previous_best_score;
+
+        self.previous_best_score = best_score;
+
+        GenerationStats {
+            generation: self.generation,
+            best_score,
+            mean_score,
+            best_delta,
+            best_apples: self.get_best_apples(),
+            best_key: self.best_individual().key,
+            clamped_weights: self.individuals.iter().map(|individual| individual.clamped_weights).sum(),
+            best_chromosomes: self.get_best_chromosomes()
+        }
+    }
+}
+
+// Islands form a ring, the `count` best individuals of every island replace the worst ones of both its neighbours
+pub fn migrate(populations: &mut [Population], count: usize) {
+    let n_of_populations = populations.len();
+
+    if n_of_populations < 2 {
+        return
+    }
+
+    let emigrants: Vec<_> = populations.iter()
+        .map(|population| population.best_individuals(count))
+        .collect();
+
+    for (i, population) in populations.iter_mut().enumerate() {
+        let immigrants = [(i + n_of_populations - 1) % n_of_populations, (i + 1) % n_of_populations].into_iter()
+            .unique()
+            .flat_map(|neighbour| emigrants[neighbour].iter().cloned())
+            .collect();
+
+        population.replace_worst(immigrants);
     }
 }
 
+// The last bucket is inclusive, so rounding that leaves the last accumulated probability just under `r` still picks it
+fn select_index(accumulated_probabilities: &[f64], r: f64) -> usize {
+    accumulated_probabilities.iter()
+        .position(|accumulated_probability| *accumulated_probability >= r)
+        .unwrap_or(accumulated_probabilities.len() - 1)
+}
+
+// Euclidean distance, e.g. how far a child moved from its parent
+pub fn genome_distance(a: &[f64], b: &[f64]) -> f64 {
+    debug_assert_eq!(a.len(), b.len(), "Genomes should have the same length");
+
+    a.iter()
+        .zip(b.iter())
+        .map(|(first, second)| (first - second).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+// Genes that differ by more than `eps`
+pub fn genes_changed(a: &[f64], b: &[f64], eps: f64) -> usize {
+    debug_assert_eq!(a.len(), b.len(), "Genomes should have the same length");
+
+    a.iter()
+        .zip(b.iter())
+        .filter(|(first, second)| (*first - *second).abs() > eps)
+        .count()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use rand::thread_rng;
+    use crate::ai::genetic_algorithm::{genes_changed, genome_distance, migrate, select_index, Evaluation, EvaluationKey, Individual, Population,
+                                       PopulationCheckpoint, PopulationOptions};
+
+    fn sum_of_abs() -> impl Fn(&Vec<f64>, &(), EvaluationKey) -> f64 + Sync + Copy {
+        |chromosomes, _args, _key| chromosomes.iter().map(|chromosome| chromosome.abs()).sum()
+    }
+
+    fn small_population() -> Population {
+        Population::new(PopulationOptions::new(10, 5, -1.0, 1.0, 0.9, 0.3, 0.3, 10).unwrap(), sum_of_abs(), &())
+    }
+
+    #[test]
+    pub fn population_options_should_reject_out_of_range_values() {
+        //when
+        let empty_population = PopulationOptions::new(0, 5, -1.0, 1.0, 0.9, 0.3, 0.3, 10);
+        let crossing_above_one = PopulationOptions::new(10, 5, -1.0, 1.0, 1.5, 0.3, 0.3, 10);
+        let negative_crossing = PopulationOptions::new(10, 5, -1.0, 1.0, -0.1, 0.3, 0.3, 10);
+        let mutation_above_one = PopulationOptions::new(10, 5, -1.0, 1.0, 0.9, 1.1, 0.3, 10);
+        let negative_mutation = PopulationOptions::new(10, 5, -1.0, 1.0, 0.9, -0.3, 0.3, 10);
+        let negative_mutation_range = PopulationOptions::new(10, 5, -1.0, 1.0, 0.9, 0.3, -0.3, 10);
+
+        //then
+        assert_eq!(empty_population.err(), Some("Population size must be greater than 0".to_string()));
+        assert_eq!(crossing_above_one.err(), Some("Crossing probability: 1.5 must be in range [0, 1]".to_string()));
+        assert_eq!(negative_crossing.err(), Some("Crossing probability: -0.1 must be in range [0, 1]".to_string()));
+        assert_eq!(mutation_above_one.err(), Some("Mutation probability: 1.1 must be in range [0, 1]".to_string()));
+        assert_eq!(negative_mutation.err(), Some("Mutation probability: -0.3 must be in range [0, 1]".to_string()));
+        assert_eq!(negative_mutation_range.err(), Some("Mutation range: -0.3 must not be negative".to_string()));
+        assert!(PopulationOptions::new(10, 5, -1.0, 1.0, 1.0, 0.0, 0.0, 10).is_ok(), "Bounds should be accepted");
+    }
+
+    #[test]
+    pub fn stats_should_sum_clamped_weights_of_population() {
+        //given
+        let clamped_of = |chromosomes: &Vec<f64>| chromosomes.iter().filter(|chromosome| chromosome.abs() > 0.5).count();
+        let evaluation_function = move |chromosomes: &Vec<f64>, _args: &(), key: EvaluationKey| Evaluation {
+            fitness: chromosomes.iter().sum(),
+            apples: 0,
+            key,
+            clamped_weights: clamped_of(chromosomes)
+        };
+        let mut population = Population::new(PopulationOptions::new(10, 5, -1.0, 1.0, 0.9, 0.3, 0.3, 3).unwrap(),
+                                             evaluation_function, &());
+
+        //when
+        let stats = population.get_stats();
+
+        //then
+        let expected: usize = population.individuals.iter().map(|individual| clamped_of(&individual.chromosomes)).sum();
+
+        assert!(expected > 0, "Some genes should be above 0.5");
+        assert_eq!(stats.clamped_weights, expected, "Clamped weights of every individual should be summed up");
+    }
 
+    #[test]
+    pub fn best_apples_should_come_from_best_genome_evaluation() {
+        //given
+        // Fitness and apples disagree on purpose, the apples of the fittest genome are tracked
+        let apples_of = |chromosomes: &Vec<f64>| (chromosomes[0].abs() * 10.0) as u32;
+        let evaluation_function = move |chromosomes: &Vec<f64>, _args: &(), key: EvaluationKey| Evaluation {
+            fitness: chromosomes.iter().map(|chromosome| chromosome.abs()).sum(),
+            apples: apples_of(chromosomes),
+            key,
+            clamped_weights: 0
+        };
+        let mut population = Population::new(PopulationOptions::new(10, 5, -1.0, 1.0, 0.9, 0.3, 0.3, 3).unwrap(),
+                                             evaluation_function, &());
+
+        //when
+        let stats = population.continue_for(3, evaluation_function, &());
+
+        //then
+        let last_stats = stats.last().unwrap();
+
+        assert_eq!(population.get_best_apples(), apples_of(&population.get_best_chromosomes()),
+                   "Tracked apples should be the ones eaten by the best genome");
+        assert_eq!(last_stats.best_apples, apples_of(&last_stats.best_chromosomes),
+                   "Generation stats should report the apples of the best genome");
+    }
+
+    #[test]
+    pub fn repair_non_finite_should_replace_nan_and_inf_genes() {
+        //given
+        let mut individual = Individual {
+            chromosomes: vec![0.5, f64::NAN, -0.25, f64::INFINITY, f64::NEG_INFINITY],
+            evaluation: 0.0,
+            apples: 0,
+            key: EvaluationKey::default(),
+            clamped_weights: 0,
+            evaluated: true
+        };
+
+        //when
+        let repaired = individual.repair_non_finite(-1.0, 1.0, &mut thread_rng());
+
+        //then
+        assert_eq!(repaired, 3, "Three genes should be replaced");
+        assert_eq!(individual.chromosomes[0], 0.5, "Finite genes shouldn't change");
+        assert_eq!(individual.chromosomes[2], -0.25, "Finite genes shouldn't change");
+
+        individual.chromosomes.iter()
+            .for_each(|chromosome| assert!(chromosome.is_finite() && *chromosome >= -1.0 && *chromosome <= 1.0,
+                                           "Every gene should be finite and in bounds"));
+    }
+
+    #[test]
+    pub fn get_best_chromosomes_should_pick_first_on_tie() {
+        //given
+        let mut population = small_population();
+        population.individuals = vec![
+            Individual {chromosomes: vec![0.1; 5], evaluation: 1.0, apples: 0, key: EvaluationKey::default(), clamped_weights: 0, evaluated: true},
+            Individual {chromosomes: vec![0.2; 5], evaluation: 3.0, apples: 0, key: EvaluationKey::default(), clamped_weights: 0, evaluated: true},
+            Individual {chromosomes: vec![0.3; 5], evaluation: 3.0, apples: 0, key: EvaluationKey::default(), clamped_weights: 0, evaluated: true},
+            Individual {chromosomes: vec![0.4; 5], evaluation: 2.0, apples: 0, key: EvaluationKey::default(), clamped_weights: 0, evaluated: true}
+        ];
+
+        //when
+        let best_chromosomes = population.get_best_chromosomes();
+
+        //then
+        assert_eq!(best_chromosomes, vec![0.2; 5], "The first of the tied individuals should be picked");
+    }
+
+    #[test]
+    pub fn peek_best_should_match_best_chromosomes_and_keep_order() {
+        //given
+        let mut population = small_population();
+        population.individuals = vec![
+            Individual {chromosomes: vec![0.1; 5], evaluation: 1.0, apples: 0, key: EvaluationKey::default(), clamped_weights: 0, evaluated: true},
+            Individual {chromosomes: vec![0.2; 5], evaluation: 2.0, apples: 0, key: EvaluationKey::default(), clamped_weights: 0, evaluated: true},
+            Individual {chromosomes: vec![0.3; 5], evaluation: 4.0, apples: 0, key: EvaluationKey::default(), clamped_weights: 0, evaluated: true},
+            Individual {chromosomes: vec![0.4; 5], evaluation: 3.0, apples: 0, key: EvaluationKey::default(), clamped_weights: 0, evaluated: true}
+        ];
+        let order_before: Vec<_> = population.individuals.iter().map(|individual| individual.evaluation).collect();
+
+        //when
+        let (best_chromosomes, best_evaluation) = population.peek_best();
+
+        //then
+        assert_eq!(best_chromosomes, population.get_best_chromosomes().as_slice(), "Both should return the same genome");
+        assert_eq!(best_evaluation, 4.0, "Evaluation of the best genome should be returned");
+
+        let order_after: Vec<_> = population.individuals.iter().map(|individual| individual.evaluation).collect();
+        assert_eq!(order_after, order_before, "Individuals should stay in the same order");
+    }
+
+    #[test]
+    pub fn migrate_should_copy_best_genome_into_both_neighbours() {
+        //given
+        let mut populations: Vec<_> = (0..3)
+            .map(|_| small_population())
+            .collect();
+        populations[0].individuals[3] = Individual {chromosomes: vec![1.0; 5], evaluation: 5.0, apples: 0, key: EvaluationKey::default(), clamped_weights: 0, evaluated: true};
+        let population_size = populations[1].individuals.len();
+
+        //when
+        migrate(&mut populations, 1);
+
+        //then
+        for neighbour in &populations[1..] {
+            assert!(neighbour.individuals.iter().any(|individual| individual.chromosomes == vec![1.0; 5]),
+                    "Best genome of the first island should be copied into its neighbour");
+            assert_eq!(neighbour.individuals.len(), population_size, "Migration shouldn't change the population size");
+        }
+        assert_eq!(populations[0].individuals[3].chromosomes, vec![1.0; 5], "Emigrant should stay on its island");
+    }
+
+    #[test]
+    pub fn unchanged_individuals_should_keep_cached_evaluation() {
+        //given
+        let counting_evaluation = |chromosomes: &Vec<f64>, evaluations: &AtomicUsize, _key: EvaluationKey| {
+            evaluations.fetch_add(1, Ordering::SeqCst);
+            chromosomes.iter().map(|chromosome| chromosome.abs()).sum::<f64>()
+        };
+        let evaluations = AtomicUsize::new(0);
+        // Without crossing and mutation every selected individual is an unchanged copy
+        let mut population = Population::new(PopulationOptions::new(10, 5, -1.0, 1.0, 0.0, 0.0, 0.3, 10).unwrap(),
+                                             counting_evaluation, &evaluations);
+
+        //when
+        population.generate_new_population(counting_evaluation, &evaluations);
+        let evaluations_after_copying = evaluations.load(Ordering::SeqCst);
+
+        population.mutation_prob = 1.0;
+        population.generate_new_population(counting_evaluation, &evaluations);
+        let evaluations_after_mutating = evaluations.load(Ordering::SeqCst);
+
+        //then
+        assert_eq!(evaluations_after_copying, 10, "Unchanged individuals shouldn't be evaluated again");
+        assert_eq!(evaluations_after_mutating, 20, "Every mutated individual should be evaluated again");
+    }
+
+    #[test]
+    pub fn resuming_from_checkpoint_should_match_uninterrupted_run() {
+        //given
+        let options = PopulationOptions::new(10, 5, -1.0, 1.0, 0.9, 0.3, 0.3, 6).unwrap().with_seed(42);
+
+        let mut uninterrupted = Population::new(options.clone(), sum_of_abs(), &());
+        uninterrupted.continue_for(6, sum_of_abs(), &());
+
+        //when
+        let mut interrupted = Population::new(options.clone(), sum_of_abs(), &());
+        interrupted.continue_for(3, sum_of_abs(), &());
+        let checkpoint: PopulationCheckpoint = interrupted.checkpoint();
+        drop(interrupted);
+
+        let mut resumed = Population::from_checkpoint(options, checkpoint);
+        resumed.continue_for(3, sum_of_abs(), &());
+
+        //then
+        assert_eq!(resumed.get_generation(), 6, "Resumed population should continue counting generations");
+        assert_eq!(resumed.get_best_chromosomes(), uninterrupted.get_best_chromosomes(),
+                   "Resumed run should end with the same champion");
+        assert_eq!(resumed.get_best_score(), uninterrupted.get_best_score(), "Resumed run should end with the same best score");
+    }
+
+    #[test]
+    pub fn select_index_should_pick_last_individual_when_probabilities_sum_under_one() {
+        //given
+        let accumulated_probabilities = [0.3, 0.6, 0.999_999_999_999];
+
+        //when-then
+        assert_eq!(select_index(&accumulated_probabilities, 1.0), 2, "Last individual should be picked for r = 1.0");
+        assert_eq!(select_index(&accumulated_probabilities, 0.0), 0, "First individual should be picked for r = 0.0");
+        assert_eq!(select_index(&accumulated_probabilities, 0.45), 1, "r should fall into the second bucket");
+    }
+
+    #[test]
+    pub fn identical_genomes_should_have_no_distance() {
+        //given
+        let genome = vec![0.5, -0.25, 1.0, 0.0];
+
+        //when-then
+        assert_eq!(genome_distance(&genome, &genome), 0.0, "Identical genomes should have distance 0");
+        assert_eq!(genes_changed(&genome, &genome, 1e-9), 0, "No gene should be changed");
+    }
+
+    #[test]
+    pub fn genomes_differing_in_two_genes_should_have_distance_of_those_genes() {
+        //given
+        let parent = vec![0.5, -0.25, 1.0, 0.0];
+        let child = vec![0.5, 2.75, 1.0, 4.0];
+
+        //when-then
+        assert_eq!(genome_distance(&parent, &child), 5.0, "Distance should be sqrt(3^2 + 4^2)");
+        assert_eq!(genes_changed(&parent, &child, 1e-9), 2, "Two genes should be changed");
+        assert_eq!(genes_changed(&parent, &child, 3.5), 1, "Changes within eps shouldn't be counted");
+    }
+
+    #[test]
+    pub fn diversity_should_be_mean_standard_deviation_of_genes() {
+        //given
+        let mut population = small_population();
+        population.individuals = vec![
+            Individual {chromosomes: vec![1.0, 0.0], evaluation: 0.0, apples: 0, key: EvaluationKey::default(), clamped_weights: 0, evaluated: true},
+            Individual {chromosomes: vec![3.0, 0.0], evaluation: 0.0, apples: 0, key: EvaluationKey::default(), clamped_weights: 0, evaluated: true}
+        ];
+
+        //when
+        let diversity = population.diversity();
+
+        //then
+        assert_eq!(diversity, 0.5, "First gene has standard deviation 1, second has 0");
+    }
+
+    #[test]
+    pub fn evolve_should_stop_once_diversity_falls_below_threshold() {
+        //given
+        // Without mutation selection and crossing quickly collapse the population into copies
+        let threshold = 0.05;
+        let population_options = PopulationOptions::new(10, 5, -1.0, 1.0, 0.9, 0.0, 0.0, 1000).unwrap()
+            .with_diversity_stop_threshold(threshold);
+        let mut population = Population::new(population_options, sum_of_abs(), &());
+
+        //when
+        let stats: Vec<_> = population.evolve(sum_of_abs(), &()).take(1000).collect();
+
+        //then
+        assert!(stats.len() < 1000, "Evolution should stop before the generation limit");
+        assert!(population.diversity() < threshold, "Population should have converged when evolution stopped");
+        assert_eq!(population.get_generation(), stats.len() as u64, "No generation should be run after converging");
+    }
+
+    #[test]
+    pub fn best_delta_should_be_difference_of_consecutive_best_scores() {
+        //given
+        let mut population = small_population();
+        let initial_best_score = population.get_best_score();
+
+        //when
+        let stats: Vec<_> = population.evolve(sum_of_abs(), &()).take(5).collect();
+
+        //then
+        assert_eq!(stats[0].best_delta, stats[0].best_score - initial_best_score,
+                   "First delta should be measured from the initial population");
+
+        stats.windows(2)
+            .for_each(|window| assert_eq!(window[1].best_delta, window[1].best_score - window[0].best_score,
+                                          "Delta should be the difference of consecutive best scores"));
+    }
+
+    #[test]
+    pub fn evolve_should_advance_one_generation_per_item() {
+        //given
+        let mut population = small_population();
+
+        //when
+        let stats: Vec<_> = population.evolve(sum_of_abs(), &()).take(3).collect();
+
+        //then
+        assert_eq!(stats.len(), 3, "There should be stats for every generation");
+
+        stats.iter()
+            .enumerate()
+            .for_each(|(i, generation_stats)| {
+                assert_eq!(generation_stats.generation, i as u64 + 1, "Generations should be consecutive");
+                assert!(generation_stats.best_score >= generation_stats.mean_score,
+                        "Best score shouldn't be lower than the mean");
+                assert_eq!(generation_stats.best_chromosomes.len(), 5, "Best genome should be complete");
+            });
+
+        assert_eq!(population.get_generation(), 3, "Population should advance three generations");
+    }
+}
@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::Path;
+use crate::ai::neural_network_utils::NeuralNetworkOptions;
+
+// Genomes are stored as a JSON array with an array of weights per genome
+pub fn save_genomes(genomes: &[Vec<f64>], path: &Path) -> Result<(), String> {
+    let content = serde_json::to_string(genomes)
+        .map_err(|error| format!("Couldn't serialize the genomes: {}", error))?;
+
+    fs::write(path, content)
+        .map_err(|error| format!("Couldn't write the genomes to {}: {}", path.display(), error))
+}
+
+pub fn load_genomes(path: &Path) -> Result<Vec<Vec<f64>>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|error| format!("Couldn't read the genomes from {}: {}", path.display(), error))?;
+
+    serde_json::from_str(&content)
+        .map_err(|error| format!("Couldn't parse the genomes from {}: {}", path.display(), error))
+}
+
+// Standalone constants for an inference-only program. The weights go layer by layer in the same order
+// as in `NeuralNetwork`, the functions are only named since their code isn't exported.
+// With `USE_BIASES` every neuron's bias follows its input weights, `INPUT_FUNCTION` is applied to the input first.
+pub fn generate_inference_code(weights: &[f64], neural_network_options: &NeuralNetworkOptions) -> Result<String, String> {
+    let expected_length = neural_network_options.weight_count();
+
+    if weights.len() != expected_length {
+        return Err(format!("Genome length: {} doesn't match network weight count: {}", weights.len(), expected_length))
+    }
+
+    if let Some(weight) = weights.iter().find(|weight| !weight.is_finite()) {
+        return Err(format!("Weight {} can't be written as a Rust literal", weight))
+    }
+
+    let layers_sizes = &neural_network_options.layers_sizes_vec;
+    let layers_functions = &neural_network_options.layers_functions;
+
+    let sizes = layers_sizes.iter()
+        .map(|size| size.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let functions = layers_functions.iter()
+        .map(|function| format!("\"{:?}\"", function))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let input_function = neural_network_options.input_function.as_ref()
+        .map_or("None".to_string(), |function| format!("Some(\"{:?}\")", function));
+
+    let weights_lines = weights.chunks(8)
+        .map(|chunk| format!("    {},", chunk.iter().map(|weight| format!("{:?}", weight)).collect::<Vec<_>>().join(", ")))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(format!("// Generated from a trained snake genome\n\
+                pub const LAYER_SIZES: [usize; {}] = [{}];\n\
+                pub const LAYER_FUNCTIONS: [&str; {}] = [{}];\n\
+                pub const INPUT_FUNCTION: Option<&str> = {};\n\
+                pub const USE_BIASES: bool = {};\n\
+                pub const WEIGHTS: [f64; {}] = [\n{}\n];\n",
+               layers_sizes.len(), sizes, layers_functions.len(), functions, input_function,
+               neural_network_options.use_biases, weights.len(), weights_lines))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs};
+    use crate::ai::genome_io::{generate_inference_code, load_genomes, save_genomes};
+    use crate::ai::neural_network_utils::{NeuralNetworkOptions, ReLU, Softmax};
+
+    #[test]
+    pub fn load_genomes_should_return_saved_genomes() {
+        //given
+        let genomes = vec![vec![0.5, -0.25, 1.0], vec![0.1, 0.2, 0.3]];
+        let path = env::temp_dir().join(format!("snake_genomes_{}.json", std::process::id()));
+
+        //when
+        save_genomes(&genomes, &path).unwrap();
+        let loaded = load_genomes(&path);
+        fs::remove_file(&path).unwrap();
+
+        //then
+        assert_eq!(loaded, Ok(genomes), "Loaded genomes should be the same as saved");
+    }
+
+    #[test]
+    pub fn generate_inference_code_should_contain_weights_and_layer_sizes() {
+        //given
+        let options = NeuralNetworkOptions::new(vec![4, 3, 2], vec![Box::new(ReLU), Box::new(Softmax)]);
+        let weights: Vec<f64> = (0..18).map(|i| i as f64 / 4.0 - 2.0).collect();
+
+        //when
+        let code = generate_inference_code(&weights, &options).unwrap();
+
+        //then
+        assert!(code.contains("pub const LAYER_SIZES: [usize; 3] = [4, 3, 2];"), "Layer sizes should be exported:\n{code}");
+        assert!(code.contains("pub const LAYER_FUNCTIONS: [&str; 2] = [\"ReLU\", \"Softmax\"];"), "Functions should be named:\n{code}");
+        assert!(code.contains("pub const INPUT_FUNCTION: Option<&str> = None;"), "Input without a function should be exported:\n{code}");
+        assert!(code.contains("pub const WEIGHTS: [f64; 18] = ["), "Weight count should be exported:\n{code}");
+
+        let (_, array) = code.split_once("WEIGHTS: [f64; 18] = [").unwrap();
+        let (array, rest) = array.split_once("];").unwrap();
+        let exported: Vec<f64> = array.split(',')
+            .map(str::trim)
+            .filter(|literal| !literal.is_empty())
+            .map(|literal| literal.parse().unwrap())
+            .collect();
+
+        assert_eq!(exported, weights, "Every weight should be written as a float literal");
+        assert_eq!(rest.trim(), "", "Weights should be the last item");
+        assert_eq!(code.matches('[').count(), code.matches(']').count(), "Brackets should be balanced");
+    }
+
+    #[test]
+    pub fn generate_inference_code_should_name_input_function() {
+        //given
+        let options = NeuralNetworkOptions::new(vec![2, 1], vec![Box::new(Softmax)]).with_input_function(Box::new(ReLU));
+
+        //when
+        let code = generate_inference_code(&[0.5, -0.5], &options).unwrap();
+
+        //then
+        assert!(code.contains("pub const INPUT_FUNCTION: Option<&str> = Some(\"ReLU\");"), "Input function should be named:\n{code}");
+    }
+
+    #[test]
+    pub fn generate_inference_code_should_reject_genome_of_wrong_length() {
+        //given
+        let options = NeuralNetworkOptions::new(vec![4, 3, 2], vec![Box::new(ReLU), Box::new(Softmax)]);
+
+        //when
+        let code = generate_inference_code(&[0.5; 3], &options);
+
+        //then
+        assert!(code.is_err(), "Genome that doesn't fit the network should be rejected");
+    }
+}
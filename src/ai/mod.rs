@@ -1,4 +1,10 @@
 pub mod genetic_algorithm;
 pub mod neural_network;
 pub mod snake_trainer;
-pub mod neural_network_utils;
\ No newline at end of file
+pub mod neural_network_utils;
+pub mod controller;
+pub mod brain;
+pub mod experiments;
+pub mod genome_io;
+#[cfg(test)]
+pub(crate) mod test_helpers;
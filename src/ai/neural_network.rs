@@ -1,17 +1,29 @@
+use std::fs;
+use std::path::Path;
 use rand::{Rng, thread_rng};
+use serde_json::{json, Value};
 use crate::ai::neural_network_utils::{Function,NeuralNetworkOptions};
 
 
 pub(crate) struct NeuralNetwork {
     layers_weights: Vec<f64>,
     layers_functions: Vec<Box<dyn Function>>,
-    layers_sizes_vec: Vec<u16>
+    layers_sizes_vec: Vec<u16>,
+    input_function: Option<Box<dyn Function>>,
+    use_biases: bool,
+    max_abs_weight: Option<f64>,
+    // How many of the current weights were out of range, the evaluation reports it with the fitness
+    clamped_weights: usize
 }
 
 impl NeuralNetwork {
     pub fn new(options: NeuralNetworkOptions) -> Result<Self, String> {
+        let capacity = options.weight_count();
         let layers_sizes_vec = options.layers_sizes_vec;
         let layers_functions = options.layers_functions;
+        let input_function = options.input_function;
+        let use_biases = options.use_biases;
+        let max_abs_weight = options.max_abs_weight;
 
         if layers_functions.len() != layers_sizes_vec.len() - 1 {
             return Err(format!("Functions len: {} must be layers len: {} - 1", layers_functions.len(),
@@ -20,10 +32,6 @@ impl NeuralNetwork {
 
         let mut rng = thread_rng();
 
-        let capacity: usize = layers_sizes_vec.windows(2)
-            .map(|window| (window[0] * window[1]) as usize)
-            .sum();
-
         let mut layers_weights = Vec::with_capacity(capacity);
 
         let mut iterator = layers_sizes_vec.iter();
@@ -31,34 +39,42 @@ impl NeuralNetwork {
 
         for (i, size) in iterator.enumerate() {
             for _neuron in 0..(*size).into() {
-                for _weight in 0..layers_sizes_vec[i] {
+                for _weight in 0..row_length(layers_sizes_vec[i] as usize, use_biases) {
                     layers_weights.push(rng.gen_range(-1.0..=1.0));
                 }
             }
         }
 
-        Ok(NeuralNetwork {layers_weights, layers_functions, layers_sizes_vec})
+        let mut neural_network = NeuralNetwork {layers_weights, layers_functions, layers_sizes_vec, input_function, use_biases,
+            max_abs_weight, clamped_weights: 0};
+        neural_network.clamp_weights();
+
+        Ok(neural_network)
     }
 
     pub fn new_with_weights(layers_weights: Vec<f64>, neural_network_options: NeuralNetworkOptions) -> Result<Self, String> {
+        let capacity = neural_network_options.weight_count();
         let layers_sizes_vec = neural_network_options.layers_sizes_vec;
         let layers_functions = neural_network_options.layers_functions;
+        let input_function = neural_network_options.input_function;
+        let use_biases = neural_network_options.use_biases;
+        let max_abs_weight = neural_network_options.max_abs_weight;
 
         if layers_functions.len() != layers_sizes_vec.len() - 1 {
             return Err(format!("Functions len: {} must be layers len: {} - 1", layers_functions.len(),
                                layers_sizes_vec.len()))
         }
 
-        let capacity: usize = layers_sizes_vec.windows(2)
-            .map(|window| (window[0] * window[1]) as usize)
-            .sum();
-
         if capacity != layers_weights.len() {
             return Err(format!("Weights len: {} and layers sizes: {:?} don't match. Expected length: {}",
                                layers_weights.len(), layers_sizes_vec, capacity))
         }
 
-        Ok(NeuralNetwork {layers_weights, layers_functions, layers_sizes_vec})
+        let mut neural_network = NeuralNetwork {layers_weights, layers_functions, layers_sizes_vec, input_function, use_biases,
+            max_abs_weight, clamped_weights: 0};
+        neural_network.clamp_weights();
+
+        Ok(neural_network)
     }
 
     pub fn get_output(&self, input: Vec<f64>) -> Result<Vec<f64>, String> {
@@ -70,13 +86,18 @@ impl NeuralNetwork {
 
         let mut output = input;
 
+        if let Some(input_function) = &self.input_function {
+            input_function.apply(&mut output);
+        }
+
         let mut layer_sizes = self.layers_sizes_vec.iter();
         let mut beginning_index = 0;
         let mut previous_layer_length = layer_sizes.next().unwrap_or(&0);
 
         for(i, layer_size) in layer_sizes.enumerate() {
-            output = calculate_output_from_layer(output, &self.layers_weights[beginning_index..beginning_index+((*layer_size * *previous_layer_length) as usize)], &self.layers_functions[i]);
-            beginning_index += (*layer_size * *previous_layer_length) as usize;
+            let layer_length = *layer_size as usize * row_length(*previous_layer_length as usize, self.use_biases);
+            output = calculate_output_from_layer(output, &self.layers_weights[beginning_index..beginning_index+layer_length], &self.layers_functions[i], self.use_biases);
+            beginning_index += layer_length;
             previous_layer_length = layer_size;
         }
 
@@ -85,15 +106,152 @@ impl NeuralNetwork {
 
     pub fn update_weights(&mut self, new_weights: Vec<f64>) {
         self.layers_weights = new_weights;
+        self.clamp_weights();
+    }
+
+    fn clamp_weights(&mut self) {
+        self.clamped_weights = self.max_abs_weight
+            .map_or(0, |max_abs_weight| clamp_weights(&mut self.layers_weights, max_abs_weight));
+    }
+
+    pub fn get_clamped_weights(&self) -> usize {
+        self.clamped_weights
+    }
+
+    pub fn get_weights(&self) -> &[f64] {
+        &self.layers_weights
+    }
+
+    pub fn get_input_size(&self) -> usize {
+        self.layers_sizes_vec.first().map_or(0, |size| *size as usize)
+    }
+
+    pub fn get_output_size(&self) -> usize {
+        self.layers_sizes_vec.last().map_or(0, |size| *size as usize)
     }
+
+    // Layer 0 connects the input layer to the first hidden layer, every neuron keeps its weights in one row.
+    // A bias is reported as the input one past the last one.
+    pub fn locate_weight(&self, index: usize) -> Result<(usize, usize, usize), String> {
+        let mut beginning_index = 0;
+
+        for (layer, sizes) in self.layers_sizes_vec.windows(2).enumerate() {
+            let (row_length, output_size) = (row_length(sizes[0] as usize, self.use_biases), sizes[1] as usize);
+
+            if index < beginning_index + row_length * output_size {
+                let offset = index - beginning_index;
+
+                return Ok((layer, offset / row_length, offset % row_length))
+            }
+
+            beginning_index += row_length * output_size;
+        }
+
+        Err(format!("Weight index: {} is out of range, the network has {} weights", index, beginning_index))
+    }
+
+    // Layer sizes are saved next to the weights, so a file from another architecture is rejected on load
+    pub fn save_to_file(&self, path: &Path) -> std::io::Result<()> {
+        let saved = json!({
+            "layers_sizes": self.layers_sizes_vec,
+            "weights": self.layers_weights
+        });
+
+        fs::write(path, saved.to_string())
+    }
+
+    pub fn load_from_file(path: &Path, neural_network_options: NeuralNetworkOptions) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|error| format!("Couldn't read the network from {}: {}", path.display(), error))?;
+
+        let mut saved: Value = serde_json::from_str(&content)
+            .map_err(|error| format!("Couldn't parse the network from {}: {}", path.display(), error))?;
+
+        let layers_sizes: Vec<u16> = serde_json::from_value(saved["layers_sizes"].take())
+            .map_err(|error| format!("Couldn't read the layers sizes from {}: {}", path.display(), error))?;
+        let layers_weights: Vec<f64> = serde_json::from_value(saved["weights"].take())
+            .map_err(|error| format!("Couldn't read the weights from {}: {}", path.display(), error))?;
+
+        if layers_sizes != neural_network_options.layers_sizes_vec {
+            return Err(format!("Saved layers sizes: {:?} and layers sizes: {:?} don't match",
+                               layers_sizes, neural_network_options.layers_sizes_vec))
+        }
+
+        NeuralNetwork::new_with_weights(layers_weights, neural_network_options)
+    }
+
+    // Dense MLP as JSON: every layer has `input_size`, `output_size`, `activation` and `weights`,
+    // a row per output neuron, so the layer is `activation(numpy.array(weights) @ x)`.
+    // Networks with biases also have `biases`, one per output neuron, added before the activation.
+    // `input_activation` is applied to the input before the first layer, null when the input goes in as it is.
+    pub fn export_portable(&self, path: &Path) -> Result<(), String> {
+        let mut beginning_index = 0;
+
+        let layers = self.layers_sizes_vec.windows(2)
+            .zip(self.layers_functions.iter())
+            .map(|(sizes, function)| {
+                let (input_size, output_size) = (sizes[0] as usize, sizes[1] as usize);
+                let row_length = row_length(input_size, self.use_biases);
+                let layer = &self.layers_weights[beginning_index..beginning_index + row_length * output_size];
+                beginning_index += row_length * output_size;
+
+                let mut exported = json!({
+                    "input_size": input_size,
+                    "output_size": output_size,
+                    "activation": format!("{:?}", function),
+                    "weights": layer.chunks(row_length).map(|row| &row[..input_size]).collect::<Vec<_>>()
+                });
+
+                if self.use_biases {
+                    exported["biases"] = json!(layer.chunks(row_length).map(|row| row[input_size]).collect::<Vec<_>>());
+                }
+
+                exported
+            })
+            .collect::<Vec<_>>();
+
+        let network = json!({
+            "format": "dense-mlp",
+            "version": 1,
+            "input_activation": self.input_function.as_ref().map(|function| format!("{:?}", function)),
+            "layers": layers
+        });
+
+        let content = serde_json::to_string_pretty(&network)
+            .map_err(|error| format!("Couldn't serialize the network: {}", error))?;
+
+        fs::write(path, content)
+            .map_err(|error| format!("Couldn't write the network to {}: {}", path.display(), error))
+    }
+}
+
+// Returns how many weights were out of range
+fn clamp_weights(weights: &mut [f64], max_abs_weight: f64) -> usize {
+    let mut clamped = 0;
+
+    for weight in weights.iter_mut().filter(|weight| weight.abs() > max_abs_weight) {
+        *weight = weight.clamp(-max_abs_weight, max_abs_weight);
+        clamped += 1;
+    }
+
+    clamped
 }
 
-fn calculate_output_from_layer(input: Vec<f64>, layer: &[f64], function: &Box<dyn Function>) -> Vec<f64> {
-    let mut output = layer.chunks(input.len())
+// Weights of a single neuron, the bias goes last
+fn row_length(input_size: usize, use_biases: bool) -> usize {
+    if use_biases {
+        input_size + 1
+    } else {
+        input_size
+    }
+}
+
+fn calculate_output_from_layer(input: Vec<f64>, layer: &[f64], function: &Box<dyn Function>, use_biases: bool) -> Vec<f64> {
+    let mut output = layer.chunks(row_length(input.len(), use_biases))
         .map(|item| item.iter()
             .zip(input.iter())
             .map(|(weight, input)| input * weight)
-            .sum())
+            .sum::<f64>() + if use_biases { item[input.len()] } else { 0.0 })
         .collect();
 
     function.apply(&mut output);
@@ -103,9 +261,75 @@ fn calculate_output_from_layer(input: Vec<f64>, layer: &[f64], function: &Box<dy
 
 #[cfg(test)]
 mod test {
-    use crate::ai::neural_network_utils::{Function,NeuralNetworkOptions, ReLU, Softmax};
+    use std::{env, fs};
+    use serde_json::Value;
+    use crate::ai::neural_network_utils::{Function, Linear, NeuralNetworkOptions, ReLU, Softmax};
     use crate::ai::neural_network::NeuralNetwork;
 
+    #[test]
+    pub fn export_portable_should_write_layer_shapes_and_weights() {
+        //given
+        let options = NeuralNetworkOptions::new(vec![3, 2, 1], vec![Box::new(ReLU), Box::new(Softmax)]);
+        let weights = vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8];
+        let neural_network = NeuralNetwork::new_with_weights(weights, options).unwrap();
+        let path = env::temp_dir().join(format!("snake_export_{}.json", std::process::id()));
+
+        //when
+        neural_network.export_portable(&path).unwrap();
+
+        //then
+        let exported: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let layers = exported["layers"].as_array().unwrap();
+
+        assert_eq!(layers.len(), 2, "There should be 2 layers");
+        assert_eq!(layers[0]["input_size"], 3, "First layer should take 3 inputs");
+        assert_eq!(layers[0]["output_size"], 2, "First layer should have 2 neurons");
+        assert_eq!(layers[0]["activation"], "ReLU", "First layer should use ReLU");
+        assert_eq!(layers[0]["weights"], serde_json::json!([[0.1, 0.2, 0.3], [0.4, 0.5, 0.6]]),
+                   "First layer should have a row of weights per neuron");
+        assert_eq!(layers[1]["activation"], "Softmax", "Second layer should use Softmax");
+        assert_eq!(layers[1]["weights"], serde_json::json!([[0.7, 0.8]]), "Second layer weights should match");
+        assert_eq!(exported["input_activation"], Value::Null, "Input without a function should be exported as it is");
+    }
+
+    #[test]
+    pub fn export_portable_should_write_input_activation() {
+        //given
+        let options = NeuralNetworkOptions::new(vec![3, 1], vec![Box::new(Softmax)]).with_input_function(Box::new(ReLU));
+        let neural_network = NeuralNetwork::new_with_weights(vec![0.1, 0.2, 0.3], options).unwrap();
+        let path = env::temp_dir().join(format!("snake_export_input_{}.json", std::process::id()));
+
+        //when
+        neural_network.export_portable(&path).unwrap();
+
+        //then
+        let exported: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(exported["input_activation"], "ReLU", "Function applied to the input should be exported");
+    }
+
+    #[test]
+    pub fn load_from_file_should_return_saved_network() {
+        //given
+        let options = NeuralNetworkOptions::new(vec![3, 2], vec![Box::new(ReLU)]);
+        let other_options = NeuralNetworkOptions::new(vec![2, 3], vec![Box::new(ReLU)]);
+        let neural_network = NeuralNetwork::new(options.clone()).unwrap();
+        let path = env::temp_dir().join(format!("snake_network_{}.json", std::process::id()));
+
+        //when
+        neural_network.save_to_file(&path).unwrap();
+        let loaded = NeuralNetwork::load_from_file(&path, options);
+        let mismatched = NeuralNetwork::load_from_file(&path, other_options);
+        fs::remove_file(&path).unwrap();
+
+        //then
+        assert_eq!(loaded.unwrap().get_weights(), neural_network.get_weights(), "Loaded weights should be the same as saved");
+        assert!(mismatched.is_err_and(|error| error.contains("[3, 2]")), "Different layers sizes should be rejected");
+    }
+
     #[test]
     pub fn new_neural_network_constructs_correct_network() {
         //given
@@ -114,7 +338,10 @@ mod test {
 
         let options = NeuralNetworkOptions {
             layers_sizes_vec: layers_sizes_vec.clone(),
-            layers_functions
+            layers_functions,
+            max_abs_weight: None,
+            input_function: None,
+            use_biases: false
         };
 
         //when
@@ -147,7 +374,10 @@ mod test {
 
         let options = NeuralNetworkOptions {
             layers_sizes_vec: layers_sizes_vec.clone(),
-            layers_functions
+            layers_functions,
+            max_abs_weight: None,
+            input_function: None,
+            use_biases: false
         };
 
         //when-then
@@ -163,7 +393,10 @@ mod test {
 
         let options = NeuralNetworkOptions {
             layers_sizes_vec: layers_sizes_vec.clone(),
-            layers_functions
+            layers_functions,
+            max_abs_weight: None,
+            input_function: None,
+            use_biases: false
         };
 
         //when
@@ -182,6 +415,56 @@ mod test {
                    "The sizes should be the same")
     }
 
+    #[test]
+    pub fn locate_weight_should_map_index_to_layer_neuron_and_input() {
+        //given
+        let options = NeuralNetworkOptions::new(vec![32, 20, 12, 4],
+                                                vec![Box::new(ReLU {}), Box::new(ReLU {}), Box::new(Softmax {})]);
+        let neural_network = NeuralNetwork::new(options).unwrap();
+
+        //when-then
+        assert_eq!(neural_network.locate_weight(0), Ok((0, 0, 0)), "First weight should be the first connection");
+        assert_eq!(neural_network.locate_weight(33), Ok((0, 1, 1)), "Second row should start after 32 inputs");
+        assert_eq!(neural_network.locate_weight(639), Ok((0, 19, 31)), "Last weight of the first layer");
+        assert_eq!(neural_network.locate_weight(640), Ok((1, 0, 0)), "Second layer should start after 32 * 20 weights");
+        assert_eq!(neural_network.locate_weight(640 + 20 * 12 + 17), Ok((2, 1, 5)), "Third layer has 12 inputs per row");
+        assert_eq!(neural_network.locate_weight(927), Ok((2, 3, 11)), "Last weight of the network");
+        assert!(neural_network.locate_weight(928).is_err(), "Index past the last weight should be an error");
+    }
+
+    #[test]
+    pub fn new_with_weights_should_clamp_weights_above_max_abs_weight() {
+        //given
+        let layers_weights = vec![0.5, -0.5, 7.0, -3.0, 2.0, -2.0, 1.0, 100.0, -100.0, 0.0];
+
+        let options = NeuralNetworkOptions::new(vec![3, 2, 2], vec![Box::new(ReLU {}), Box::new(Softmax {})])
+            .with_max_abs_weight(2.0);
+
+        //when
+        let neural_network = NeuralNetwork::new_with_weights(layers_weights, options).unwrap();
+
+        //then
+        assert_eq!(neural_network.layers_weights, vec![0.5, -0.5, 2.0, -2.0, 2.0, -2.0, 1.0, 2.0, -2.0, 0.0],
+                   "Weights above the cap should be clamped to +-2.0");
+        assert_eq!(neural_network.get_clamped_weights(), 4, "Network should count the weights it clamped");
+    }
+
+    #[test]
+    pub fn new_and_update_weights_should_respect_max_abs_weight() {
+        //given
+        let options = NeuralNetworkOptions::new(vec![3, 2], vec![Box::new(ReLU)]).with_max_abs_weight(0.5);
+
+        //when
+        let mut neural_network = NeuralNetwork::new(options).unwrap();
+        let random_weights = neural_network.layers_weights.clone();
+        neural_network.update_weights(vec![0.1, 3.0, -3.0, 0.2, 0.5, -0.6]);
+
+        //then
+        assert!(random_weights.iter().all(|weight| weight.abs() <= 0.5), "Random weights should be clamped to +-0.5");
+        assert_eq!(neural_network.layers_weights, vec![0.1, 0.5, -0.5, 0.2, 0.5, -0.5],
+                   "Updated weights should be clamped like the ones passed to new_with_weights");
+    }
+
     #[test]
     pub fn new_with_weights_should_return_err_on_incorrect_options() {
         //given
@@ -191,7 +474,10 @@ mod test {
 
         let options = NeuralNetworkOptions {
             layers_sizes_vec: layers_sizes_vec.clone(),
-            layers_functions
+            layers_functions,
+            max_abs_weight: None,
+            input_function: None,
+            use_biases: false
         };
 
         //when-then
@@ -207,13 +493,56 @@ mod test {
 
         let options = NeuralNetworkOptions {
             layers_sizes_vec: layers_sizes_vec.clone(),
-            layers_functions
+            layers_functions,
+            max_abs_weight: None,
+            input_function: None,
+            use_biases: false
         };
 
         //when-then
         assert!(NeuralNetwork::new_with_weights(layers_weights, options).is_err(), "There should be an error");
     }
 
+    #[derive(Debug, Clone)]
+    struct ClampToUnit;
+
+    impl Function for ClampToUnit {
+        fn apply(&self, input: &mut Vec<f64>) {
+            input.iter_mut().for_each(|number| *number = number.clamp(-1.0, 1.0));
+        }
+    }
+
+    #[test]
+    pub fn input_function_should_transform_input_before_first_layer() {
+        //given
+        let options = NeuralNetworkOptions::new(vec![2, 1], vec![Box::new(Linear)])
+            .with_input_function(Box::new(ClampToUnit));
+        let neural_network = NeuralNetwork::new_with_weights(vec![1.0, 1.0], options).unwrap();
+
+        //when
+        let output = neural_network.get_output(vec![5.0, 0.5]).unwrap();
+
+        //then
+        assert_eq!(output, vec![1.5], "First layer should see the clamped input 1.0 and 0.5");
+    }
+
+    #[test]
+    pub fn biases_should_shift_neuron_output() {
+        //given
+        let options = NeuralNetworkOptions::new(vec![2, 1], vec![Box::new(Linear)]).with_biases(true);
+        let too_short = NeuralNetworkOptions::new(vec![2, 1], vec![Box::new(Linear)]).with_biases(true);
+        let neural_network = NeuralNetwork::new_with_weights(vec![1.0, 2.0, 0.5], options).unwrap();
+
+        //when
+        let output = neural_network.get_output(vec![1.0, 1.0]).unwrap();
+
+        //then
+        assert_eq!(output, vec![3.5], "Bias should be added to the weighted sum");
+        assert_eq!(neural_network.locate_weight(2), Ok((0, 0, 2)), "Bias should follow the neuron's input weights");
+        assert!(NeuralNetwork::new_with_weights(vec![1.0, 2.0], too_short).is_err(),
+                "Genome without the bias should be rejected");
+    }
+
     #[test]
     pub fn get_output_should_calculate_correctly() {
         //given
@@ -223,7 +552,10 @@ mod test {
 
         let options = NeuralNetworkOptions {
             layers_sizes_vec: layers_sizes_vec.clone(),
-            layers_functions
+            layers_functions,
+            max_abs_weight: None,
+            input_function: None,
+            use_biases: false
         };
 
         let neural_network = match NeuralNetwork::new_with_weights(layers_weights.clone(), options) {
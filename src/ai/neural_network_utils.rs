@@ -1,6 +1,7 @@
+use std::f64::consts::PI;
 use std::fmt::Debug;
 
-pub trait Function: Debug + FunctionClone + Sync {
+pub trait Function: Debug + FunctionClone + Send + Sync {
     fn apply(&self, input: &mut Vec<f64>);
 }
 
@@ -54,17 +55,345 @@ impl Function for Softmax {
     }
 }
 
+// Identity, leaves the raw logits which can all be negative
+#[derive(Debug, Clone)]
+pub struct Linear;
+
+impl Function for Linear {
+    fn apply(&self, _input: &mut Vec<f64>) {}
+}
+
+// Negative inputs go through exp(x) / (1 + exp(x)) so exp never overflows
+#[derive(Debug, Clone)]
+pub struct Sigmoid;
+
+impl Function for Sigmoid {
+    fn apply(&self, input: &mut Vec<f64>) {
+        for number in input.iter_mut() {
+            *number = if *number >= 0.0 {
+                1.0 / (1.0 + (-*number).exp())
+            } else {
+                let exp = number.exp();
+                exp / (1.0 + exp)
+            };
+        }
+    }
+}
+
+// Tanh approximation of GELU
+#[derive(Debug, Clone)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct GELU;
+
+impl Function for GELU {
+    fn apply(&self, input: &mut Vec<f64>) {
+        let scale = (2.0 / PI).sqrt();
+
+        for number in input.iter_mut() {
+            *number = 0.5 * *number * (1.0 + (scale * (*number + 0.044715 * number.powi(3))).tanh());
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct NeuralNetworkOptions {
     pub layers_sizes_vec: Vec<u16>,
-    pub layers_functions: Vec<Box<dyn Function>>
+    pub layers_functions: Vec<Box<dyn Function>>,
+    pub max_abs_weight: Option<f64>,
+    // Applied to the raw sensor input before the first layer, e.g. to clamp or rescale it
+    pub input_function: Option<Box<dyn Function>>,
+    // Every neuron gets a bias after its input weights, genomes saved without biases need it off
+    pub use_biases: bool
 }
 
 impl NeuralNetworkOptions {
     pub fn new(layers_sizes_vec: Vec<u16>, layers_functions: Vec<Box<dyn Function>>) -> Self {
         NeuralNetworkOptions {
             layers_sizes_vec,
-            layers_functions
+            layers_functions,
+            max_abs_weight: None,
+            input_function: None,
+            use_biases: false
+        }
+    }
+
+    // Weights passed to `NeuralNetwork::new_with_weights` are clamped to [-max_abs_weight, max_abs_weight]
+    pub fn with_max_abs_weight(mut self, max_abs_weight: f64) -> Self {
+        self.max_abs_weight = Some(max_abs_weight);
+        self
+    }
+
+    pub fn with_input_function(mut self, input_function: Box<dyn Function>) -> Self {
+        self.input_function = Some(input_function);
+        self
+    }
+
+    pub fn with_biases(mut self, use_biases: bool) -> Self {
+        self.use_biases = use_biases;
+        self
+    }
+
+    // Length of the genome, includes the biases when they're used
+    pub fn weight_count(&self) -> usize {
+        if self.use_biases {
+            self.weight_count_with_biases()
+        } else {
+            self.connection_count()
+        }
+    }
+
+    fn connection_count(&self) -> usize {
+        self.layers_sizes_vec.windows(2)
+            .map(|window| window[0] as usize * window[1] as usize)
+            .sum()
+    }
+
+    // One extra weight for every neuron outside of the input layer
+    pub fn weight_count_with_biases(&self) -> usize {
+        self.connection_count() + self.layers_sizes_vec.iter()
+            .skip(1)
+            .map(|size| *size as usize)
+            .sum::<usize>()
+    }
+}
+
+#[derive(Default)]
+pub struct NetworkBuilder {
+    input_size: Option<u16>,
+    layers_sizes_vec: Vec<u16>,
+    layers_functions: Vec<Box<dyn Function>>,
+    has_output: bool
+}
+
+impl NetworkBuilder {
+    pub fn new() -> Self {
+        NetworkBuilder::default()
+    }
+
+    pub fn input(mut self, size: u16) -> Self {
+        self.input_size = Some(size);
+        self
+    }
+
+    pub fn hidden(mut self, size: u16, function: Box<dyn Function>) -> Self {
+        self.layers_sizes_vec.push(size);
+        self.layers_functions.push(function);
+        self
+    }
+
+    pub fn output(mut self, size: u16, function: Box<dyn Function>) -> Self {
+        self.has_output = true;
+        self.hidden(size, function)
+    }
+
+    pub fn build(self) -> Result<NeuralNetworkOptions, String> {
+        let input_size = match self.input_size {
+            Some(size) => size,
+            None => return Err("Input layer size must be set".to_string())
+        };
+
+        if !self.has_output {
+            return Err("Output layer must be set".to_string())
+        }
+
+        let mut layers_sizes_vec = Vec::with_capacity(self.layers_sizes_vec.len() + 1);
+        layers_sizes_vec.push(input_size);
+        layers_sizes_vec.extend(self.layers_sizes_vec);
+
+        Ok(NeuralNetworkOptions::new(layers_sizes_vec, self.layers_functions))
+    }
+}
+
+// Shannon entropy of a probability distribution, e.g. Softmax output
+pub fn output_entropy(output: &[f64]) -> f64 {
+    -output.iter()
+        .filter(|probability| **probability > 0.0)
+        .map(|probability| probability * probability.ln())
+        .sum::<f64>()
+}
+
+// Weights outside of [-1, 1] are clamped to the edges of the range
+pub fn quantize_weights(weights: &[f64], bits: u8) -> Vec<u16> {
+    let max_level = quantization_max_level(bits);
+
+    weights.iter()
+        .map(|weight| (((weight.clamp(-1.0, 1.0) + 1.0) / 2.0) * max_level).round() as u16)
+        .collect()
+}
+
+pub fn dequantize_weights(quantized: &[u16], bits: u8) -> Vec<f64> {
+    let max_level = quantization_max_level(bits);
+
+    quantized.iter()
+        .map(|level| (*level as f64 / max_level) * 2.0 - 1.0)
+        .collect()
+}
+
+pub fn quantization_step(bits: u8) -> f64 {
+    2.0 / quantization_max_level(bits)
+}
+
+fn quantization_max_level(bits: u8) -> f64 {
+    assert!(bits > 0 && bits <= 16, "Bits: {} must be between 1 and 16", bits);
+
+    ((1_u32 << bits) - 1) as f64
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ai::neural_network::NeuralNetwork;
+    use crate::ai::neural_network_utils::{dequantize_weights, output_entropy, quantization_step, quantize_weights, Function, NetworkBuilder, GELU, NeuralNetworkOptions, ReLU, Sigmoid, Softmax};
+    use crate::ai::snake_trainer::FIRST_LAYER_SIZE;
+
+    #[test]
+    pub fn gelu_should_match_reference_values() {
+        //given
+        let mut input = vec![0.0, 1.0, -1.0, 2.0, -3.0];
+        let expected = [0.0, 0.8411919906, -0.1588080094, 1.9545976941, -0.0036373921];
+
+        //when
+        GELU.apply(&mut input);
+
+        //then
+        for (value, expected_value) in input.iter().zip(expected.iter()) {
+            assert!((value - expected_value).abs() < 1e-8, "Expected {expected_value}, got {value}");
         }
     }
+
+    #[test]
+    pub fn sigmoid_should_saturate_without_overflow() {
+        //given
+        let mut input = vec![0.0, 1000.0, -1000.0];
+
+        //when
+        Sigmoid.apply(&mut input);
+
+        //then
+        assert_eq!(input[0], 0.5, "Sigmoid of 0 should be 0.5");
+        assert!((input[1] - 1.0).abs() < 1e-12, "Large positive input should saturate near 1, got {}", input[1]);
+        assert!(input[2] >= 0.0 && input[2] < 1e-12, "Large negative input should saturate near 0, got {}", input[2]);
+        assert!(input.iter().all(|value| value.is_finite()), "Sigmoid shouldn't overflow");
+    }
+
+    #[test]
+    pub fn network_builder_should_match_manual_options() {
+        //given
+        let manual_options = NeuralNetworkOptions::new(
+            vec![FIRST_LAYER_SIZE as u16, 20, 12, 4],
+            vec![Box::new(ReLU), Box::new(ReLU), Box::new(Softmax)]
+        );
+
+        //when
+        let options = NetworkBuilder::new()
+            .input(FIRST_LAYER_SIZE as u16)
+            .hidden(20, Box::new(ReLU))
+            .hidden(12, Box::new(ReLU))
+            .output(4, Box::new(Softmax))
+            .build()
+            .unwrap();
+
+        //then
+        assert_eq!(options.layers_sizes_vec, manual_options.layers_sizes_vec,
+                   "The sizes should be the same");
+        assert_eq!(format!("{:?}", options.layers_functions), format!("{:?}", manual_options.layers_functions),
+                   "The functions should be the same");
+        assert_eq!(options.weight_count(), FIRST_LAYER_SIZE * 20 + 20 * 12 + 12 * 4,
+                   "Weight count should match the manual calculation");
+    }
+
+    #[test]
+    pub fn weight_count_with_biases_should_add_one_weight_per_neuron() {
+        //given
+        let options = NeuralNetworkOptions::new(
+            vec![FIRST_LAYER_SIZE as u16, 20, 12, 4],
+            vec![Box::new(ReLU), Box::new(ReLU), Box::new(Softmax)]
+        );
+
+        //when
+        let weight_count = options.weight_count();
+        let weight_count_with_biases = options.weight_count_with_biases();
+
+        //then
+        assert_eq!(weight_count, FIRST_LAYER_SIZE * 20 + 20 * 12 + 12 * 4,
+                   "Weight count shouldn't include biases");
+        assert_eq!(weight_count_with_biases, weight_count + 20 + 12 + 4,
+                   "There should be one bias for every non input neuron");
+    }
+
+    #[test]
+    pub fn network_builder_should_return_error_without_input_or_output() {
+        //when-then
+        assert!(NetworkBuilder::new().output(4, Box::new(Softmax)).build().is_err(),
+                "There should be an error without input layer");
+        assert!(NetworkBuilder::new().input(4).hidden(3, Box::new(ReLU)).build().is_err(),
+                "There should be an error without output layer");
+    }
+
+    #[test]
+    pub fn output_entropy_should_be_near_ln_n_for_uniform_output() {
+        //given
+        let output = vec![0.25, 0.2501, 0.2499, 0.25];
+
+        //when
+        let entropy = output_entropy(&output);
+
+        //then
+        assert!((entropy - 4.0_f64.ln()).abs() < 0.0001, "{entropy} should be close to ln(4)");
+    }
+
+    #[test]
+    pub fn output_entropy_should_be_near_zero_for_one_hot_output() {
+        //given
+        let output = vec![0.0, 1.0, 0.0, 0.0];
+
+        //when
+        let entropy = output_entropy(&output);
+
+        //then
+        assert!(entropy.abs() < 0.0001, "{entropy} should be close to 0");
+    }
+
+    #[test]
+    pub fn dequantized_weights_should_be_within_quantization_step() {
+        //given
+        let weights = vec![-1.0, -0.731, -0.25, 0.0, 0.1234, 0.5, 0.999, 1.0];
+        let bits = 8;
+
+        //when
+        let restored = dequantize_weights(&quantize_weights(&weights, bits), bits);
+
+        //then
+        let max_error = quantization_step(bits) / 2.0;
+
+        weights.iter()
+            .zip(restored.iter())
+            .for_each(|(original, restored)| assert!((original - restored).abs() <= max_error,
+                                                     "{restored} should be within {max_error} of {original}"));
+    }
+
+    #[test]
+    pub fn network_from_dequantized_weights_should_give_similar_output() {
+        //given
+        let layers_sizes_vec = vec![4, 3, 2];
+        let layers_functions: Vec<Box<dyn Function>> = vec![Box::new(ReLU), Box::new(Softmax)];
+        let options = NeuralNetworkOptions::new(layers_sizes_vec, layers_functions);
+        let weights = vec![0.1, -0.2, 0.3, -0.4, 0.5, -0.6, 0.7, -0.8, 0.9,
+                           -0.15, 0.25, -0.35, 0.45, -0.55, 0.65, -0.75, 0.85, -0.95];
+        let bits = 12;
+
+        let original = NeuralNetwork::new_with_weights(weights.clone(), options.clone()).unwrap();
+        let quantized = NeuralNetwork::new_with_weights(
+            dequantize_weights(&quantize_weights(&weights, bits), bits), options).unwrap();
+
+        let input = vec![0.3, 0.6, 1.0, 0.0];
+
+        //when
+        let original_output = original.get_output(input.clone()).unwrap();
+        let quantized_output = quantized.get_output(input).unwrap();
+
+        //then
+        original_output.iter()
+            .zip(quantized_output.iter())
+            .for_each(|(a, b)| assert!((a - b).abs() < 0.005, "{b} should be close to {a}"));
+    }
 }
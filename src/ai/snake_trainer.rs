@@ -1,22 +1,299 @@
 use std::cmp::{max_by};
-use rand::{Rng, thread_rng};
-use crate::ai::genetic_algorithm::{Population, PopulationOptions};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::mem::size_of;
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::mpsc::Sender;
+use std::thread;
+use rand::{Rng, SeedableRng, thread_rng};
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::rngs::{SmallRng, StdRng};
+use rand::seq::SliceRandom;
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use crate::ai::brain::Brain;
+use crate::ai::controller::{Controller, NeuralController};
+use crate::ai::genetic_algorithm::{migrate, Evaluation, EvaluationKey, GenerationStats, Population, PopulationOptions};
 use crate::ai::neural_network::NeuralNetwork;
-use crate::ai::neural_network_utils::NeuralNetworkOptions;
-use crate::snake::snake_game::{Ate, Direction, DistanceInfo, Food, Position, Snake};
-use crate::visualisation::game_constants::{MAX_DISTANCE, MAX_X_DISTANCE, MAX_Y_DISTANCE, GRID_SIZE};
-use crate::visualisation::ml_game::play_game_with_ml;
+use crate::ai::neural_network_utils::{NetworkBuilder, NeuralNetworkOptions, ReLU, Softmax};
+use crate::snake::food_spawner::{AdversarialFoodSpawner, FoodSpawner, PinnedFirstFoodSpawner, SeededFoodSpawner};
+use crate::snake::game_simulation::{GameSimulation, MAX_STEPS_WITHOUT_APPLE};
+use crate::snake::snake_game::{Board, Direction, DistanceInfo, Distances, Food, Position, Snake};
+use crate::snake::symmetry::{distances_with_vectors, ray_by_vector, Symmetry};
+use crate::visualisation::game_constants::{FPS, MAX_DISTANCE, MAX_X_DISTANCE, MAX_Y_DISTANCE, GRID_SIZE};
+use crate::visualisation::ml_game::{best_genome_index, play_game_with_ml, play_game_with_ml_live, PlaybackMode};
+use crate::visualisation::replay::{record_game, save_replay, GameFrame};
 
 pub const FIRST_LAYER_SIZE: usize = 32;
-pub const OUTPUT_LAYER_SIZE: usize = 3;
 
-const MAX_STEPS_WITHOUT_APPLE: f64 = 150.0;
+// Order of the network input: wall/apple/body for each of the 8 directions, then head and tail direction one-hots
+pub const SENSOR_LAYOUT: [&str; FIRST_LAYER_SIZE] = [
+    "top_wall", "top_apple", "top_body",
+    "right_wall", "right_apple", "right_body",
+    "bottom_wall", "bottom_apple", "bottom_body",
+    "left_wall", "left_apple", "left_body",
+    "top_right_wall", "top_right_apple", "top_right_body",
+    "bottom_right_wall", "bottom_right_apple", "bottom_right_body",
+    "bottom_left_wall", "bottom_left_apple", "bottom_left_body",
+    "top_left_wall", "top_left_apple", "top_left_body",
+    "head_up", "head_right", "head_down", "head_left",
+    "tail_up", "tail_right", "tail_down", "tail_left"
+];
+pub const OUTPUT_LAYER_SIZE: usize = 3;
 
 const POINTS_BASE: f64 = 2.0;
 
+pub const BEST_NETWORK_PATH: &str = "best_network.json";
+
+pub const BEST_REPLAY_PATH: &str = "best_replay.txt";
+
+// (mean, standard deviation) of every input. Wall distances are averaged over all head positions on the board,
+// directions are assumed to be equally likely, apple and body flags are left as they are.
+static INPUT_STATISTICS: Lazy<Vec<(f64, f64)>> = Lazy::new(calculate_input_statistics);
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum FoodPlacement {
+    #[default]
+    Random,
+    Adversarial
+}
+
+// The sensor input doesn't fit the first layer of the network
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct InputSizeMismatch {
+    pub expected: usize,
+    pub actual: usize
+}
+
+impl Display for InputSizeMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Network input len: {} doesn't match network first layer size: {}", self.actual, self.expected)
+    }
+}
+
+// Binary only tells if the apple/body is seen in a direction, Distance gives 1 / steps to it
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum VisionMode {
+    #[default]
+    Binary,
+    Distance
+}
+
+// How the board is turned into the network input, the demo has to use the same settings as training
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct InputSettings {
+    pub standardize: bool,
+    pub vision_mode: VisionMode,
+    // The last `history_frames` sensor readings are fed to the network together, 1 is only the current one
+    pub history_frames: usize
+}
+
+impl InputSettings {
+    pub fn input_size(&self) -> usize {
+        FIRST_LAYER_SIZE * self.history_frames
+    }
+
+    // The first layer has to take exactly the frames these settings build
+    pub fn check_input_layer(&self, neural_network_options: &NeuralNetworkOptions) -> Result<(), String> {
+        match neural_network_options.layers_sizes_vec.first() {
+            Some(size) if *size as usize != self.input_size() => {
+                Err(format!("Input layer size: {} doesn't match {} input frames of {} sensors", size,
+                            self.history_frames, FIRST_LAYER_SIZE))
+            },
+            _ => Ok(())
+        }
+    }
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        InputSettings {standardize: false, vision_mode: VisionMode::default(), history_frames: 1}
+    }
+}
+
+// Recent sensor frames of a single game, stacked newest first into the network input
+#[derive(Clone, PartialEq, Debug)]
+pub struct InputHistory {
+    frames: VecDeque<Vec<f64>>,
+    capacity: usize
+}
+
+impl InputHistory {
+    pub fn new(capacity: usize) -> Self {
+        InputHistory {frames: VecDeque::with_capacity(capacity), capacity: capacity.max(1)}
+    }
+
+    // The input `frame` would give, until enough frames are seen the oldest one is repeated
+    pub fn stacked_with(&self, frame: &[f64]) -> Vec<f64> {
+        let mut stacked = frame.to_vec();
+
+        for i in 0..self.capacity - 1 {
+            let previous = self.frames.get(i).or(self.frames.back()).map_or(frame, |previous| previous.as_slice());
+
+            stacked.extend_from_slice(previous);
+        }
+
+        stacked
+    }
+
+    pub fn push(&mut self, frame: Vec<f64>) -> Vec<f64> {
+        let stacked = self.stacked_with(&frame);
+
+        self.frames.push_front(frame);
+        self.frames.truncate(self.capacity - 1);
+
+        stacked
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
+
+// Greedy takes the best move, Sample draws a move from the network output treated as probabilities
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum DecisionPolicy {
+    #[default]
+    Greedy,
+    Sample
+}
+
+// How the survival term grows with the steps, Linear keeps it proportional
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum SurvivalDecay {
+    #[default]
+    Linear,
+    // ln(1 + steps), every extra step is worth less than the previous one
+    Logarithmic,
+    // Steps above the cap aren't rewarded at all
+    Capped(f64)
+}
+
+impl SurvivalDecay {
+    fn apply(&self, steps: f64) -> f64 {
+        match self {
+            SurvivalDecay::Linear => steps,
+            SurvivalDecay::Logarithmic => steps.ln_1p(),
+            SurvivalDecay::Capped(cap) => steps.min(*cap)
+        }
+    }
+}
+
+// fitness = survival_weight * survival_decay(steps) + apple_weight * apple reward - penalty for slow apples
+//           - wall_hugging_penalty * steps along a wall + length_milestone_bonus * new maximum lengths reached
+//           + coverage_weight * distinct cells visited + occupancy_bonus once the snake takes up occupancy_threshold of the board
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct FitnessWeights {
+    pub survival_weight: f64,
+    pub apple_weight: f64,
+    pub wall_hugging_penalty: f64,
+    pub length_milestone_bonus: f64,
+    pub coverage_weight: f64,
+    pub occupancy_threshold: f64,
+    pub occupancy_bonus: f64,
+    pub survival_decay: SurvivalDecay
+}
+
+impl FitnessWeights {
+    pub fn new(survival_weight: f64, apple_weight: f64) -> Self {
+        FitnessWeights {survival_weight, apple_weight, wall_hugging_penalty: 0.0, length_milestone_bonus: 0.0,
+            coverage_weight: 0.0, occupancy_threshold: 1.0, occupancy_bonus: 0.0, survival_decay: SurvivalDecay::Linear}
+    }
+
+    pub fn with_wall_hugging_penalty(mut self, wall_hugging_penalty: f64) -> Self {
+        self.wall_hugging_penalty = wall_hugging_penalty;
+        self
+    }
+
+    pub fn with_length_milestone_bonus(mut self, length_milestone_bonus: f64) -> Self {
+        self.length_milestone_bonus = length_milestone_bonus;
+        self
+    }
+
+    // Rewards exploring the board over circling in a tight loop near the apple
+    pub fn with_coverage_weight(mut self, coverage_weight: f64) -> Self {
+        self.coverage_weight = coverage_weight;
+        self
+    }
+
+    // Paid once per game, should outweigh a few apples since filling the board is the hardest part
+    pub fn with_occupancy_bonus(mut self, occupancy_threshold: f64, occupancy_bonus: f64) -> Self {
+        self.occupancy_threshold = occupancy_threshold;
+        self.occupancy_bonus = occupancy_bonus;
+        self
+    }
+
+    // Stops stalling genomes from outscoring the ones that eat once the snake can survive for a long time
+    pub fn with_survival_decay(mut self, survival_decay: SurvivalDecay) -> Self {
+        self.survival_decay = survival_decay;
+        self
+    }
+}
+
+impl Default for FitnessWeights {
+    fn default() -> Self {
+        FitnessWeights::new(1.0, 1.0)
+    }
+}
+
+// Where the snake starts in every evaluated game, a fixed spawn makes A/B comparisons of genomes fairer
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum SpawnMode {
+    #[default]
+    Random,
+    Center,
+    Fixed(Position)
+}
+
+// Every `interval` generations the `count` best genomes of each island are copied into its neighbours
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Migration {
+    pub interval: u64,
+    pub count: usize
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EvaluationSettings {
+    pub food_placement: FoodPlacement,
+    pub random_symmetry: bool,
+    pub fitness_weights: FitnessWeights,
+    pub input_settings: InputSettings,
+    pub decision_policy: DecisionPolicy,
+    pub seed: Option<u64>,
+    pub hunger_interval: Option<u32>,
+    pub first_food: Option<Position>,
+    pub spawn_mode: SpawnMode,
+    pub mask_unsafe: bool,
+    pub random_facing: bool
+}
+
+// Everything a single evaluation reads, passed through the population as its evaluation args
+#[derive(Clone)]
+pub struct EvalContext {
+    pub neural_network_options: NeuralNetworkOptions,
+    pub evaluation_settings: EvaluationSettings,
+    // Every game of the run is derived from it and the evaluation key, drawn at random when the settings have no seed
+    pub seed: u64
+}
+
+impl EvalContext {
+    pub fn new(neural_network_options: NeuralNetworkOptions) -> Self {
+        EvalContext {neural_network_options, evaluation_settings: EvaluationSettings::default(), seed: thread_rng().gen()}
+    }
+}
+
+#[derive(Clone)]
 pub struct MLSnakeOptions {
     genetic_algorithm_options: PopulationOptions,
-    neural_network_options: NeuralNetworkOptions
+    neural_network_options: NeuralNetworkOptions,
+    keep_last: Option<usize>,
+    evaluation_settings: EvaluationSettings,
+    evaluation_threads: Option<usize>,
+    migration: Option<Migration>
 }
 
 impl MLSnakeOptions {
@@ -24,165 +301,1842 @@ impl MLSnakeOptions {
                neural_network_options: NeuralNetworkOptions) -> Self {
         MLSnakeOptions {
             genetic_algorithm_options,
-            neural_network_options
+            neural_network_options,
+            keep_last: None,
+            evaluation_settings: EvaluationSettings::default(),
+            evaluation_threads: None,
+            migration: None
         }
     }
-}
 
-pub struct SnakeTrainer;
+    pub fn builder() -> MLSnakeOptionsBuilder {
+        MLSnakeOptionsBuilder::default()
+    }
 
-impl SnakeTrainer {
-    pub fn train(options: MLSnakeOptions) {
-        let n_of_generations = options.genetic_algorithm_options.n_of_generations;
-        let mut population = Population::new(options.genetic_algorithm_options, evaluate, &options.neural_network_options);
+    pub fn with_food_placement(mut self, food_placement: FoodPlacement) -> Self {
+        self.evaluation_settings.food_placement = food_placement;
+        self
+    }
 
-        let mut populations = Vec::with_capacity((n_of_generations + 1) as usize);
+    // Every evaluated game is played on a randomly rotated/mirrored board
+    pub fn with_random_symmetry(mut self, random_symmetry: bool) -> Self {
+        self.evaluation_settings.random_symmetry = random_symmetry;
+        self
+    }
 
-        for i in 0..n_of_generations {
-            println!("Generation: {}", i+1);
-            population.generate_new_population(evaluate, &options.neural_network_options);
-            println!("Best score: {}", population.get_best_score());
-            populations.push(population.get_best_chromosomes());
-        }
+    // Every evaluated game starts facing a random direction instead of always to the right
+    pub fn with_random_facing(mut self, random_facing: bool) -> Self {
+        self.evaluation_settings.random_facing = random_facing;
+        self
+    }
 
-        println!("Best of the best: {:?}", populations[populations.len()-1]);
+    pub fn with_decision_policy(mut self, decision_policy: DecisionPolicy) -> Self {
+        self.evaluation_settings.decision_policy = decision_policy;
+        self
+    }
 
-        play_game_with_ml(options.neural_network_options, populations).unwrap()
+    // Speeds up early training, but the networks no longer have to learn to avoid walls and their own body
+    pub fn with_unsafe_move_masking(mut self, mask_unsafe: bool) -> Self {
+        self.evaluation_settings.mask_unsafe = mask_unsafe;
+        self
+    }
+
+    // Network inputs are shifted to zero mean and unit variance before they're fed to the network
+    pub fn with_input_standardization(mut self, standardize_input: bool) -> Self {
+        self.evaluation_settings.input_settings.standardize = standardize_input;
+        self
+    }
+
+    pub fn with_vision_mode(mut self, vision_mode: VisionMode) -> Self {
+        self.evaluation_settings.input_settings.vision_mode = vision_mode;
+        self
+    }
+
+    // Short-term memory without a recurrent network, the first layer needs `history_frames` * FIRST_LAYER_SIZE inputs
+    pub fn with_input_history(mut self, history_frames: usize) -> Self {
+        self.evaluation_settings.input_settings.history_frames = history_frames;
+        self
     }
-}
 
-pub fn evaluate(chromosomes: &Vec<f64>, neural_network_options: &NeuralNetworkOptions) -> f64 {
-    let neural_network = NeuralNetwork::new_with_weights(chromosomes.clone(),
-                                                         (*neural_network_options).clone()).unwrap();
+    pub fn with_fitness_weights(mut self, fitness_weights: FitnessWeights) -> Self {
+        self.evaluation_settings.fitness_weights = fitness_weights;
+        self
+    }
+
+    // Uses all the cores when not set
+    pub fn with_evaluation_threads(mut self, evaluation_threads: usize) -> Self {
+        self.evaluation_threads = Some(evaluation_threads);
+        self
+    }
+
+    // Every evaluation thread gets its own rng derived from this seed, without it the rngs are seeded from entropy
+    pub fn with_evaluation_seed(mut self, seed: u64) -> Self {
+        self.evaluation_settings.seed = Some(seed);
+        self
+    }
+
+    // Every individual of every generation starts with the first apple at `position`, later apples stay random
+    pub fn with_pinned_first_food(mut self, position: Position) -> Self {
+        self.evaluation_settings.first_food = Some(position);
+        self
+    }
+
+    pub fn with_spawn_mode(mut self, spawn_mode: SpawnMode) -> Self {
+        self.evaluation_settings.spawn_mode = spawn_mode;
+        self
+    }
+
+    // Harder mode where the snake shrinks every `hunger_interval` steps without food
+    pub fn with_hunger_interval(mut self, hunger_interval: u32) -> Self {
+        self.evaluation_settings.hunger_interval = Some(hunger_interval);
+        self
+    }
+
+    // Only the best genomes of the last `keep_last` generations are kept for the demo
+    pub fn with_keep_last(mut self, keep_last: usize) -> Self {
+        self.keep_last = Some(keep_last);
+        self
+    }
+
+    // Only used by `SnakeTrainer::train_multi`, islands stay independent without it
+    pub fn with_migration(mut self, interval: u64, count: usize) -> Self {
+        self.migration = Some(Migration {interval, count});
+        self
+    }
 
-    let snake_pos = generate_random_position_with_distance(2);
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(0) | None = self.neural_network_options.layers_sizes_vec.last() {
+            return Err("Output layer size must be greater than 0".to_string())
+        }
 
-    let mut snake = Snake::new(snake_pos);
+        if self.keep_last == Some(0) {
+            return Err("Keep last must be greater than 0".to_string())
+        }
 
-    let mut food = generate_new_food(&snake);
+        let occupancy_threshold = self.evaluation_settings.fitness_weights.occupancy_threshold;
 
-    let mut input = generate_network_input(&snake, &food);
+        if occupancy_threshold <= 0.0 || occupancy_threshold > 1.0 {
+            return Err(format!("Occupancy threshold: {} must be in range (0, 1]", occupancy_threshold))
+        }
 
-    let mut game_over = false;
-    let mut steps: f64 = 0.0;
-    let mut steps_without_apple = 0.0;
-    let mut score: f64 = 0.0;
+        if let SurvivalDecay::Capped(cap) = self.evaluation_settings.fitness_weights.survival_decay {
+            if cap <= 0.0 {
+                return Err(format!("Survival cap: {} must be greater than 0", cap))
+            }
+        }
 
-    while !game_over && steps_without_apple < MAX_STEPS_WITHOUT_APPLE {
-        steps += 1.0;
-        steps_without_apple += 1.0;
+        if self.evaluation_settings.hunger_interval == Some(0) {
+            return Err("Hunger interval must be greater than 0".to_string())
+        }
 
-        let output = neural_network.get_output(input).unwrap();
+        if let SpawnMode::Fixed(position) = self.evaluation_settings.spawn_mode {
+            Snake::try_new(position, Board::default())?;
 
-        let move_dir = interpret_network_output(&output);
+            if self.evaluation_settings.random_facing && Board::default().is_next_to_wall(position) {
+                return Err(format!("Snake at ({}, {}) doesn't fit on the board with its body in every facing", position.x, position.y))
+            }
+        }
 
-        snake.move_in_dir(move_dir);
+        if let Some(position) = self.evaluation_settings.first_food {
+            if !Board::default().contains(position) {
+                return Err(format!("Pinned first food at ({}, {}) is off the board", position.x, position.y))
+            }
+        }
 
-        snake.update_state(&food);
+        if let Some(migration) = self.migration {
+            if migration.interval == 0 {
+                return Err("Migration interval must be greater than 0".to_string())
+            }
 
-        if let Some(ate) = snake.get_ate() {
-            match ate {
-                Ate::Food => {
-                    food = generate_new_food(&snake);
-                    score += 1.0;
-                    steps_without_apple = 0.0;
-                },
-                Ate::Itself | Ate::Border => game_over = true
+            if migration.count == 0 || migration.count > self.genetic_algorithm_options.population_size {
+                return Err(format!("Migration count: {} must be in range [1, {}]", migration.count,
+                                   self.genetic_algorithm_options.population_size))
             }
         }
 
-        input = generate_network_input(&snake, &food);
+        if self.evaluation_threads == Some(0) {
+            return Err("Number of evaluation threads must be greater than 0".to_string())
+        }
+
+        let input_settings = self.evaluation_settings.input_settings;
+
+        if input_settings.history_frames == 0 {
+            return Err("Input history must have at least 1 frame".to_string())
+        }
+
+        input_settings.check_input_layer(&self.neural_network_options)?;
+
+        let expected_chromosomes = self.neural_network_options.weight_count();
+
+        if self.genetic_algorithm_options.number_of_chromosomes != expected_chromosomes {
+            return Err(format!("Number of chromosomes: {} doesn't match network weight count: {}",
+                               self.genetic_algorithm_options.number_of_chromosomes, expected_chromosomes))
+        }
+
+        Ok(())
     }
 
-    max_by(steps + POINTS_BASE.powf(score) + score.powf(2.1)*500.0 - (score.powf(1.2) * (steps * 0.25).powf(1.3)), 0.0, |a, b| a.total_cmp(b))
-}
+    pub fn get_neural_network_options(&self) -> &NeuralNetworkOptions {
+        &self.neural_network_options
+    }
 
-pub fn generate_random_position() -> Position {
-    let mut rng = thread_rng();
+    pub fn get_input_settings(&self) -> InputSettings {
+        self.evaluation_settings.input_settings
+    }
 
-    Position::new(rng.gen_range(0..GRID_SIZE.0), rng.gen_range(0..GRID_SIZE.1))
-}
+    pub fn eval_context(&self) -> EvalContext {
+        EvalContext {
+            neural_network_options: self.neural_network_options.clone(),
+            evaluation_settings: self.evaluation_settings,
+            seed: self.evaluation_settings.seed.unwrap_or_else(|| thread_rng().gen())
+        }
+    }
 
-fn generate_random_position_with_distance(distance_from_walls: i16) -> Position {
-    let mut rng = thread_rng();
+    // The same seed always plays the same game, so fitness of different genomes can be compared directly
+    pub fn evaluate_seeded(&self, chromosomes: &[f64], seed: u64) -> f64 {
+        evaluate_with::<NeuralNetwork, _>(chromosomes, &self.neural_network_options, &self.evaluation_settings,
+                                          &mut StdRng::seed_from_u64(seed))
+    }
 
-    Position::new(rng.gen_range(0+distance_from_walls..GRID_SIZE.0-distance_from_walls),
-                  rng.gen_range(0+distance_from_walls..GRID_SIZE.1-distance_from_walls))
+    fn evaluation_pool(&self) -> ThreadPool {
+        ThreadPoolBuilder::new()
+            .num_threads(self.evaluation_threads.unwrap_or(0))
+            .build()
+            .unwrap()
+    }
 }
 
-pub fn generate_new_food(snake: &Snake) -> Food {
-    let mut position = generate_random_position();
+// Only the fields that differ from the defaults have to be set, the number of chromosomes follows the network
+pub struct MLSnakeOptionsBuilder {
+    population_size: usize,
+    gene_range: (f64, f64),
+    crossing_prob: f64,
+    mutation_prob: f64,
+    mutation_range: f64,
+    n_of_generations: u64,
+    neural_network_options: Option<NeuralNetworkOptions>
+}
 
-    while snake.is_in_position(position) {
-        position = generate_random_position();
+impl Default for MLSnakeOptionsBuilder {
+    fn default() -> Self {
+        MLSnakeOptionsBuilder {
+            population_size: 500,
+            gene_range: (-1.0, 1.0),
+            crossing_prob: 0.9,
+            mutation_prob: 0.3,
+            mutation_range: 0.3,
+            n_of_generations: 2000,
+            neural_network_options: None
+        }
     }
-
-    Food::new(position)
 }
 
-pub fn generate_network_input(snake: &Snake, food: &Food) -> Vec<f64> {
-    let distances = snake.get_distances(food);
+impl MLSnakeOptionsBuilder {
+    pub fn population_size(mut self, population_size: usize) -> Self {
+        self.population_size = population_size;
+        self
+    }
 
-    let mut input = Vec::with_capacity(FIRST_LAYER_SIZE);
+    pub fn gene_range(mut self, min_val: f64, max_val: f64) -> Self {
+        self.gene_range = (min_val, max_val);
+        self
+    }
 
-    add_distance_to_input(distances.top, &mut input, MAX_Y_DISTANCE);
-    add_distance_to_input(distances.right, &mut input, MAX_X_DISTANCE);
-    add_distance_to_input(distances.bottom, &mut input, MAX_Y_DISTANCE);
-    add_distance_to_input(distances.left, &mut input, MAX_X_DISTANCE);
+    pub fn crossing_prob(mut self, crossing_prob: f64) -> Self {
+        self.crossing_prob = crossing_prob;
+        self
+    }
 
-    add_distance_to_input(distances.top_right, &mut input, *MAX_DISTANCE);
-    add_distance_to_input(distances.bottom_right, &mut input, *MAX_DISTANCE);
-    add_distance_to_input(distances.bottom_left, &mut input, *MAX_DISTANCE);
-    add_distance_to_input(distances.top_left, &mut input, *MAX_DISTANCE);
+    pub fn mutation_prob(mut self, mutation_prob: f64) -> Self {
+        self.mutation_prob = mutation_prob;
+        self
+    }
 
-    match snake.get_current_direction() {
-        Direction::UP => input.append(&mut vec![1.0, 0.0, 0.0, 0.0]),
-        Direction::RIGHT => input.append(&mut vec![0.0, 1.0, 0.0, 0.0]),
-        Direction::DOWN => input.append(&mut vec![0.0, 0.0, 1.0, 0.0]),
-        Direction::LEFT => input.append(&mut vec![0.0, 0.0, 0.0, 1.0]),
+    pub fn mutation_range(mut self, mutation_range: f64) -> Self {
+        self.mutation_range = mutation_range;
+        self
     }
 
-    match snake.get_tail_direction() {
-        Direction::UP => input.append(&mut vec![1.0, 0.0, 0.0, 0.0]),
-        Direction::RIGHT => input.append(&mut vec![0.0, 1.0, 0.0, 0.0]),
-        Direction::DOWN => input.append(&mut vec![0.0, 0.0, 1.0, 0.0]),
-        Direction::LEFT => input.append(&mut vec![0.0, 0.0, 0.0, 1.0]),
+    pub fn n_of_generations(mut self, n_of_generations: u64) -> Self {
+        self.n_of_generations = n_of_generations;
+        self
     }
 
-    input
-}
+    pub fn neural_network_options(mut self, neural_network_options: NeuralNetworkOptions) -> Self {
+        self.neural_network_options = Some(neural_network_options);
+        self
+    }
 
-fn add_distance_to_input(distance: DistanceInfo, input: &mut Vec<f64>, max: f64) {
-    input.push(distance.distance_to_wall / max);
-    input.push(distance.distance_to_apple);
-    input.push(distance.distance_to_body);
+    pub fn build(self) -> Result<MLSnakeOptions, String> {
+        let neural_network_options = match self.neural_network_options {
+            Some(neural_network_options) => neural_network_options,
+            None => NetworkBuilder::new()
+                .input(FIRST_LAYER_SIZE as u16)
+                .hidden(20, Box::new(ReLU))
+                .hidden(12, Box::new(ReLU))
+                .output(4, Box::new(Softmax))
+                .build()?
+        };
+
+        let population_options = PopulationOptions::new(self.population_size, neural_network_options.weight_count(),
+                                                        self.gene_range.0, self.gene_range.1, self.crossing_prob,
+                                                        self.mutation_prob, self.mutation_range, self.n_of_generations)?;
+
+        let options = MLSnakeOptions::new(population_options, neural_network_options);
+
+        options.validate()?;
+
+        Ok(options)
+    }
 }
 
-pub enum Move {
-    FORWARD,
-    LEFT,
-    RIGHT
+pub struct SnakeTrainer;
+
+// An independently evolved population with the best genome of each of its generations
+struct Island {
+    population: Population,
+    best_genomes: Vec<Vec<f64>>
 }
 
-pub fn interpret_network_output(output: &Vec<f64>) -> Direction {
-    let mut max = 0.0;
-    let mut index = 0;
+impl SnakeTrainer {
+    // The demo plays the default range of the last generations when `playback_mode` is None
+    pub fn train(options: MLSnakeOptions, playback_mode: Option<PlaybackMode>) {
+        let neural_network_options = options.neural_network_options.clone();
+        let input_settings = options.evaluation_settings.input_settings;
+        let eval_context = options.eval_context();
 
-    for (i, val) in output.iter().enumerate() {
-        if *val > max {
-            max = *val;
-            index = i;
+        let (populations, evaluations) = SnakeTrainer::train_best_genomes(options, &eval_context);
+
+        let best_index = best_genome_index(populations.len(), &evaluations);
+
+        println!("Best of the best: {:?}", populations[best_index]);
+
+        let best_network = NeuralNetwork::new_with_weights(populations[best_index].clone(),
+                                                           neural_network_options.clone()).unwrap();
+
+        match best_network.save_to_file(Path::new(BEST_NETWORK_PATH)) {
+            Ok(()) => println!("Saved the best network to {}", BEST_NETWORK_PATH),
+            Err(error) => println!("Couldn't save the best network to {}: {}", BEST_NETWORK_PATH, error)
+        }
+
+        let frames = record_evaluation_game(&populations[best_index], &eval_context, evaluations[best_index].key);
+
+        match save_replay(&frames, Path::new(BEST_REPLAY_PATH)) {
+            Ok(()) => println!("Saved a game of the best network to {}", BEST_REPLAY_PATH),
+            Err(error) => println!("{}", error)
         }
+
+        play_game_with_ml(neural_network_options, populations, evaluations, playback_mode, input_settings, FPS, false).unwrap()
     }
 
-    if index == 0 {
-        Direction::UP
-    } else if index == 1 {
-        Direction::RIGHT
-    } else if index == 2 {
-        Direction::DOWN
-    } else {
-        Direction::LEFT
+    // Plays a network saved with `NeuralNetwork::save_to_file`, e.g. the one `train` leaves behind.
+    // The file only has the weights, so the network is scored again on a training game for the demo
+    pub fn replay(path: &Path, options: MLSnakeOptions, playback_mode: Option<PlaybackMode>) {
+        let neural_network_options = options.neural_network_options.clone();
+        let weights = NeuralNetwork::load_from_file(path, neural_network_options.clone()).unwrap()
+            .get_weights()
+            .to_vec();
+
+        let evaluation = evaluate_with_apples(&weights, &options.eval_context(), EvaluationKey {generation: 0, index: 0});
+
+        play_game_with_ml(neural_network_options, vec![weights], vec![evaluation], playback_mode, options.evaluation_settings.input_settings,
+                          FPS, false).unwrap()
     }
-}
 
+    // Runs the genetic algorithm without the demo window, the returned population can be evolved further with `continue_for`
+    pub fn train_headless(options: &MLSnakeOptions) -> Population {
+        options.validate().unwrap();
+
+        options.evaluation_pool().install(|| {
+            let eval_context = options.eval_context();
+            let mut population = Population::new(options.genetic_algorithm_options.clone(), evaluate_with_apples, &eval_context);
+
+            population.continue_for(options.genetic_algorithm_options.n_of_generations, evaluate_with_apples, &eval_context);
+
+            population
+        })
+    }
+
+    // Best genome of every kept generation together with how it was evaluated
+    fn train_best_genomes(options: MLSnakeOptions, eval_context: &EvalContext) -> (Vec<Vec<f64>>, Vec<Evaluation>) {
+        options.validate().unwrap();
+
+        options.evaluation_pool().install(|| SnakeTrainer::collect_best_genomes(options, eval_context))
+    }
+
+    fn collect_best_genomes(options: MLSnakeOptions, eval_context: &EvalContext) -> (Vec<Vec<f64>>, Vec<Evaluation>) {
+        let n_of_generations = options.genetic_algorithm_options.n_of_generations;
+        let board = Board::default();
+        let (max_apples, max_fitness) = (max_apples(board), max_possible_fitness(board, &eval_context.evaluation_settings.fitness_weights));
+        let mut population = Population::new(options.genetic_algorithm_options, evaluate_with_apples, eval_context);
+
+        let capacity = options.keep_last.unwrap_or(n_of_generations as usize);
+        let mut populations = VecDeque::with_capacity(capacity + 1);
+
+        for stats in population.evolve(evaluate_with_apples, eval_context).take(n_of_generations as usize) {
+            report_generation(&stats, max_apples, max_fitness);
+
+            report_clamped_weights(stats.generation, stats.clamped_weights, &eval_context.neural_network_options);
+
+            let evaluation = Evaluation {fitness: stats.best_score, apples: stats.best_apples, key: stats.best_key, ..Default::default()};
+
+            populations.push_back((stats.best_chromosomes, evaluation));
+
+            if populations.len() > capacity {
+                populations.pop_front();
+            }
+        }
+
+        let stored_bytes: usize = populations.iter()
+            .map(|(chromosomes, _)| chromosomes.len() * size_of::<f64>())
+            .sum();
+
+        println!("Stored best genomes: {} (~{} KiB)", populations.len(), stored_bytes / 1024);
+
+        populations.into_iter().unzip()
+    }
+
+    // Evolves `islands` independent populations in parallel and returns the best genomes of the one with the best final score
+    pub fn train_multi(options: MLSnakeOptions, islands: usize) -> Vec<Vec<f64>> {
+        let islands = SnakeTrainer::train_islands(&options, islands);
+
+        islands.iter()
+            .enumerate()
+            .for_each(|(i, island)| println!("Island {}: best score {}, {} apples", i, island.population.get_best_score(),
+                                              island.population.get_best_apples()));
+
+        champion_island(islands).best_genomes
+    }
+
+    // Islands evolve in lockstep so they can exchange genomes between generations
+    fn train_islands(options: &MLSnakeOptions, n_of_islands: usize) -> Vec<Island> {
+        options.validate().unwrap();
+
+        let n_of_generations = options.genetic_algorithm_options.n_of_generations;
+
+        options.evaluation_pool().install(|| {
+            let eval_context = options.eval_context();
+
+            let mut populations: Vec<_> = (0..n_of_islands)
+                .into_par_iter()
+                .map(|_| Population::new(options.genetic_algorithm_options.clone(), evaluate_with_apples, &eval_context))
+                .collect();
+
+            let mut best_genomes = vec![Vec::new(); n_of_islands];
+
+            for generation in 1..=n_of_generations {
+                let stats: Vec<_> = populations.par_iter_mut()
+                    .map(|population| population.evolve(evaluate_with_apples, &eval_context).next())
+                    .collect();
+
+                if stats.iter().all(Option::is_none) {
+                    break
+                }
+
+                let clamped_weights = stats.iter().flatten().map(|stats| stats.clamped_weights).sum();
+
+                report_clamped_weights(generation, clamped_weights, &eval_context.neural_network_options);
+
+                for (genomes, stats) in best_genomes.iter_mut().zip(stats) {
+                    if let Some(stats) = stats {
+                        genomes.push(stats.best_chromosomes);
+                    }
+                }
+
+                if let Some(migration) = options.migration {
+                    if generation.is_multiple_of(migration.interval) {
+                        migrate(&mut populations, migration.count);
+                    }
+                }
+            }
+
+            populations.into_iter()
+                .zip(best_genomes)
+                .map(|(population, best_genomes)| Island {population, best_genomes})
+                .collect()
+        })
+    }
+
+    // Trains on a background thread while the demo window plays the most recent best genome.
+    pub fn train_live(options: MLSnakeOptions) {
+        let (sender, receiver) = mpsc::channel();
+        let neural_network_options = options.neural_network_options.clone();
+        let input_settings = options.evaluation_settings.input_settings;
+
+        thread::spawn(move || SnakeTrainer::train_with_sender(options, sender));
+
+        play_game_with_ml_live(neural_network_options, receiver, input_settings).unwrap()
+    }
+
+    // Sends `(generation, evaluation of the best, best chromosomes)` after every generation. Stops early once the receiver is gone.
+    fn train_with_sender(options: MLSnakeOptions, sender: Sender<(u64, Evaluation, Vec<f64>)>) {
+        options.validate().unwrap();
+
+        options.evaluation_pool().install(|| SnakeTrainer::send_best_genomes(options, sender))
+    }
+
+    fn send_best_genomes(options: MLSnakeOptions, sender: Sender<(u64, Evaluation, Vec<f64>)>) {
+        let n_of_generations = options.genetic_algorithm_options.n_of_generations;
+        let eval_context = options.eval_context();
+        let board = Board::default();
+        let (max_apples, max_fitness) = (max_apples(board), max_possible_fitness(board, &eval_context.evaluation_settings.fitness_weights));
+        let mut population = Population::new(options.genetic_algorithm_options, evaluate_with_apples, &eval_context);
+
+        for stats in population.evolve(evaluate_with_apples, &eval_context).take(n_of_generations as usize) {
+            report_generation(&stats, max_apples, max_fitness);
+
+            report_clamped_weights(stats.generation, stats.clamped_weights, &eval_context.neural_network_options);
+
+            let evaluation = Evaluation {fitness: stats.best_score, apples: stats.best_apples, key: stats.best_key, ..Default::default()};
+
+            if sender.send((stats.generation, evaluation, stats.best_chromosomes)).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+// The fitness grows exponentially with the apples, so the apples show the progress of weak genomes better than the percentage
+fn report_generation(stats: &GenerationStats, max_apples: u32, max_fitness: f64) {
+    println!("Generation: {}", stats.generation);
+    println!("Best score: {} ({:+}), {:.3e}% of a perfect game, {}/{} apples", stats.best_score, stats.best_delta,
+             100.0 * stats.best_score / max_fitness, stats.best_apples, max_apples);
+
+    if stats.best_delta < 0.0 {
+        println!("Warning: best score dropped by {} in generation {}", -stats.best_delta, stats.generation);
+    }
+}
+
+// Networks clamp their weights on the evaluation threads, the count is only printed once the generation is done
+fn report_clamped_weights(generation: u64, clamped: usize, neural_network_options: &NeuralNetworkOptions) {
+    if let (true, Some(max_abs_weight)) = (clamped > 0, neural_network_options.max_abs_weight) {
+        println!("Clamped {} weights to +-{} in generation {}", clamped, max_abs_weight, generation);
+    }
+}
+
+// On ties the island that comes first wins
+fn champion_island(islands: Vec<Island>) -> Island {
+    islands.into_iter()
+        .reduce(|best, island| if island.population.get_best_score().total_cmp(&best.population.get_best_score()).is_gt() { island } else { best })
+        .unwrap_or_else(|| panic!("There should be at least one island"))
+}
+
+#[allow(clippy::ptr_arg)]
+pub fn evaluate(chromosomes: &Vec<f64>, eval_context: &EvalContext, key: EvaluationKey) -> f64 {
+    evaluate_breakdown(chromosomes, eval_context, key).total
+}
+
+// Same game as `evaluate`, the population keeps the apples to show them next to the fitness
+#[allow(clippy::ptr_arg)]
+pub fn evaluate_with_apples(chromosomes: &Vec<f64>, eval_context: &EvalContext, key: EvaluationKey) -> Evaluation {
+    let breakdown = evaluate_breakdown(chromosomes, eval_context, key);
+
+    Evaluation {fitness: breakdown.total, apples: breakdown.apples, key, clamped_weights: breakdown.clamped_weights}
+}
+
+// Same game as `evaluate`, but every term of the fitness is returned, for tuning the fitness weights
+pub fn evaluate_breakdown(chromosomes: &[f64], eval_context: &EvalContext, key: EvaluationKey) -> FitnessBreakdown {
+    evaluate_breakdown_with::<NeuralNetwork, _>(chromosomes, &eval_context.neural_network_options, &eval_context.evaluation_settings,
+                                                &mut game_rng_for(eval_context.seed, key))
+}
+
+// Each game owns one rng for all of its steps. It isn't kept per worker thread: which games a worker plays
+// depends on the scheduling, so a reused worker rng would make the games differ between runs with the same seed.
+// Deriving it from the evaluated individual keeps every game reproducible, also to replay it after training.
+fn game_rng_for(seed: u64, key: EvaluationKey) -> SmallRng {
+    SmallRng::seed_from_u64(seed ^ (key.generation + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (key.index as u64 + 1).wrapping_mul(0xC2B2_AE3D_27D4_EB4F))
+}
+
+fn evaluate_with<B: Brain, R: Rng>(chromosomes: &[f64], neural_network_options: &NeuralNetworkOptions,
+                                   evaluation_settings: &EvaluationSettings, rng: &mut R) -> f64 {
+    evaluate_breakdown_with::<B, R>(chromosomes, neural_network_options, evaluation_settings, rng).total
+}
+
+// Same game as `evaluate_breakdown` with the same key, every frame is kept so it can be watched with `play_replay`
+pub fn record_evaluation_game(chromosomes: &[f64], eval_context: &EvalContext, key: EvaluationKey) -> Vec<GameFrame> {
+    let (mut controller, simulation) = evaluation_game::<NeuralNetwork, _>(chromosomes, &eval_context.neural_network_options,
+                                                                           &eval_context.evaluation_settings,
+                                                                           &mut game_rng_for(eval_context.seed, key));
+
+    record_game(&mut controller, simulation)
+}
+
+fn evaluate_breakdown_with<B: Brain, R: Rng>(chromosomes: &[f64], neural_network_options: &NeuralNetworkOptions,
+                                             evaluation_settings: &EvaluationSettings, rng: &mut R) -> FitnessBreakdown {
+    let (mut controller, simulation) = evaluation_game::<B, R>(chromosomes, neural_network_options, evaluation_settings, rng);
+    let clamped_weights = controller.get_clamped_weights();
+
+    FitnessBreakdown {clamped_weights, ..evaluate_game_breakdown(&mut controller, simulation, &evaluation_settings.fitness_weights)}
+}
+
+// The controller and the starting board of a training game, both take their randomness from `rng`
+fn evaluation_game<B: Brain, R: Rng>(chromosomes: &[f64], neural_network_options: &NeuralNetworkOptions,
+                                     evaluation_settings: &EvaluationSettings, rng: &mut R) -> (NeuralController<B>, GameSimulation) {
+    let brain = B::from_weights(chromosomes.to_vec(), (*neural_network_options).clone()).unwrap();
+
+    let mut controller = NeuralController::new(brain);
+
+    if evaluation_settings.random_symmetry {
+        controller = controller.with_symmetry(Symmetry::random(rng));
+    }
+
+    controller = controller.with_input_settings(evaluation_settings.input_settings);
+
+    if evaluation_settings.decision_policy == DecisionPolicy::Sample {
+        controller = controller.with_sampling(StdRng::from_rng(&mut *rng).unwrap());
+    }
+
+    if evaluation_settings.mask_unsafe {
+        controller = controller.with_unsafe_move_masking();
+    }
+
+    let mut food_spawner = food_spawner_for(evaluation_settings, rng);
+
+    let snake = spawn_snake(evaluation_settings, rng);
+
+    let food = food_spawner.spawn(&snake);
+
+    let simulation = GameSimulation::with_food_spawner(snake, food, food_spawner);
+
+    (controller, simulation)
+}
+
+fn spawn_snake<R: Rng>(evaluation_settings: &EvaluationSettings, rng: &mut R) -> Snake {
+    let snake_pos = spawn_position(evaluation_settings.spawn_mode, rng);
+
+    let facing = if evaluation_settings.random_facing {
+        *[Direction::UP, Direction::LEFT, Direction::RIGHT, Direction::DOWN].choose(rng).unwrap()
+    } else {
+        Direction::RIGHT
+    };
+
+    let snake = Snake::new_facing(snake_pos, facing);
+
+    match evaluation_settings.hunger_interval {
+        Some(hunger_interval) => snake.with_hunger(hunger_interval),
+        None => snake
+    }
+}
+
+fn spawn_position<R: Rng>(spawn_mode: SpawnMode, rng: &mut R) -> Position {
+    match spawn_mode {
+        SpawnMode::Random => generate_random_position_with_distance(2, rng),
+        SpawnMode::Center => Position::new(GRID_SIZE.0 / 2, GRID_SIZE.1 / 2),
+        SpawnMode::Fixed(position) => position
+    }
+}
+
+fn food_spawner_for<R: Rng>(evaluation_settings: &EvaluationSettings, rng: &mut R) -> Box<dyn FoodSpawner> {
+    let food_spawner: Box<dyn FoodSpawner> = match evaluation_settings.food_placement {
+        FoodPlacement::Random => Box::new(SeededFoodSpawner::new(rng.gen())),
+        FoodPlacement::Adversarial => Box::new(AdversarialFoodSpawner)
+    };
+
+    match evaluation_settings.first_food {
+        Some(position) => Box::new(PinnedFirstFoodSpawner::new(position, food_spawner)),
+        None => food_spawner
+    }
+}
+
+pub fn play_until_end<C: Controller>(controller: &mut C, simulation: &mut GameSimulation) {
+    while simulation.get_end_reason().is_none() {
+        let move_dir = controller.decide(simulation.get_snake(), simulation.get_food());
+
+        simulation.step(move_dir);
+    }
+}
+
+// Plays the simulation to the end with the given controller and scores the game
+fn evaluate_game<C: Controller>(controller: &mut C, simulation: GameSimulation, fitness_weights: &FitnessWeights) -> f64 {
+    evaluate_game_breakdown(controller, simulation, fitness_weights).total
+}
+
+fn evaluate_game_breakdown<C: Controller>(controller: &mut C, mut simulation: GameSimulation, fitness_weights: &FitnessWeights) -> FitnessBreakdown {
+    play_until_end(controller, &mut simulation);
+
+    let breakdown = FitnessBreakdown::new(simulation.get_steps() as f64, simulation.get_score() as f64, fitness_weights);
+    let wall_hugging_penalty = fitness_weights.wall_hugging_penalty * simulation.get_wall_hugging_steps() as f64;
+    let length_milestone_bonus = fitness_weights.length_milestone_bonus * simulation.get_length_milestones() as f64;
+    let coverage_bonus = fitness_weights.coverage_weight * simulation.get_visited_cells() as f64;
+    let occupancy_bonus = if simulation.get_max_occupancy() >= fitness_weights.occupancy_threshold {
+        fitness_weights.occupancy_bonus
+    } else {
+        0.0
+    };
+
+    let bonus_term = length_milestone_bonus + coverage_bonus + occupancy_bonus - wall_hugging_penalty;
+
+    breakdown.with_bonus(bonus_term)
+}
+
+// The snake starts with its head and one segment, the rest of the board can be filled with apples
+pub fn max_apples(board: Board) -> u32 {
+    (board.width as u32 * board.height as u32).saturating_sub(2)
+}
+
+// Upper bound for a single game on `board`: every apple is eaten using the whole step budget for each of them,
+// no penalty is paid and every bonus is collected. Games with hunger can eat more apples than the board holds.
+pub fn max_possible_fitness(board: Board, fitness_weights: &FitnessWeights) -> f64 {
+    let cells = board.width as f64 * board.height as f64;
+    let max_score = max_apples(board) as f64;
+    let max_steps = (max_score + 1.0) * MAX_STEPS_WITHOUT_APPLE as f64;
+
+    let survival_term = fitness_weights.survival_weight.max(0.0) * fitness_weights.survival_decay.apply(max_steps);
+    let apple_term = fitness_weights.apple_weight.max(0.0) * (POINTS_BASE.powf(max_score) + max_score.powf(2.1)*500.0);
+    let length_milestone_bonus = fitness_weights.length_milestone_bonus.max(0.0) * max_score;
+    let coverage_bonus = fitness_weights.coverage_weight.max(0.0) * cells;
+
+    survival_term + apple_term + length_milestone_bonus + coverage_bonus + fitness_weights.occupancy_bonus.max(0.0)
+}
+
+// total = survival_term + apple_term - penalty_term + bonus_term, unless it had to be raised to 0
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct FitnessBreakdown {
+    pub survival_term: f64,
+    pub apple_term: f64,
+    // Paid for eating slowly
+    pub penalty_term: f64,
+    // Length milestone, coverage and occupancy bonuses minus the wall hugging penalty
+    pub bonus_term: f64,
+    pub total: f64,
+    pub apples: u32,
+    // Weights of the genome clamped by the network, they don't change the fitness
+    pub clamped_weights: usize
+}
+
+impl FitnessBreakdown {
+    fn new(steps: f64, score: f64, fitness_weights: &FitnessWeights) -> Self {
+        let survival_term = fitness_weights.survival_weight * fitness_weights.survival_decay.apply(steps);
+        let apple_term = fitness_weights.apple_weight * (POINTS_BASE.powf(score) + score.powf(2.1)*500.0);
+        let penalty_term = score.powf(1.2) * (steps * 0.25).powf(1.3);
+
+        FitnessBreakdown {survival_term, apple_term, penalty_term, bonus_term: 0.0, total: 0.0, apples: score as u32, clamped_weights: 0}
+            .with_bonus(0.0)
+    }
+
+    // The total is clamped once over every term, a bonus can't lift a negative base above 0 on its own
+    fn with_bonus(mut self, bonus_term: f64) -> Self {
+        self.bonus_term = bonus_term;
+        self.total = max_by(self.survival_term + self.apple_term - self.penalty_term + bonus_term, 0.0, |a, b| a.total_cmp(b));
+        self
+    }
+}
+
+fn calculate_fitness(steps: f64, score: f64, fitness_weights: &FitnessWeights) -> f64 {
+    FitnessBreakdown::new(steps, score, fitness_weights).total
+}
+
+// Starts from x = 1 so the initial body segment of a snake spawned here stays on the board
+pub fn generate_random_position<R: Rng>(rng: &mut R) -> Position {
+    Position::new(rng.gen_range(1..GRID_SIZE.0), rng.gen_range(0..GRID_SIZE.1))
+}
+
+fn generate_random_position_with_distance<R: Rng>(distance_from_walls: i16, rng: &mut R) -> Position {
+    Position::new(rng.gen_range(0+distance_from_walls..GRID_SIZE.0-distance_from_walls),
+                  rng.gen_range(0+distance_from_walls..GRID_SIZE.1-distance_from_walls))
+}
+
+pub fn generate_new_food(snake: &Snake) -> Option<Food> {
+    generate_new_food_on_board(snake, &snake.get_board(), &mut thread_rng())
+}
+
+// Picks from the free cells instead of retrying random ones, returns None when the board is full
+pub fn generate_new_food_on_board<R: Rng>(snake: &Snake, board: &Board, rng: &mut R) -> Option<Food> {
+    let free_cells = (0..board.height)
+        .flat_map(|y| (0..board.width).map(move |x| Position::new(x, y)))
+        .filter(|position| !snake.is_in_position(*position))
+        .collect::<Vec<_>>();
+
+    free_cells.choose(rng).map(|position| Food::new(*position))
+}
+
+pub fn generate_network_input(snake: &Snake, food: &Food) -> Vec<f64> {
+    generate_network_input_with_symmetry(snake, food, Symmetry::IDENTITY)
+}
+
+pub fn generate_network_input_with_symmetry(snake: &Snake, food: &Food, symmetry: Symmetry) -> Vec<f64> {
+    generate_network_input_with_settings(snake, food, symmetry, &InputSettings::default())
+}
+
+pub fn generate_network_input_with_settings(snake: &Snake, food: &Food, symmetry: Symmetry,
+                                            input_settings: &InputSettings) -> Vec<f64> {
+    let mut distances = snake.get_distances(food);
+
+    if input_settings.vision_mode == VisionMode::Distance {
+        add_distance_vision(&mut distances, snake, food);
+    }
+
+    let distances = symmetry.transform_distances(&distances);
+
+    let mut input = build_input_from_distances(&distances, symmetry.transform_direction(snake.get_current_direction()),
+                                               symmetry.transform_direction(snake.get_tail_direction()));
+
+    if input_settings.standardize {
+        standardize_input(&mut input);
+    }
+
+    input
+}
+
+// Replaces the apple and body flags with 1 / number of steps to the first apple/body cell in every direction
+fn add_distance_vision(distances: &mut Distances, snake: &Snake, food: &Food) {
+    let board = Board::default();
+    let head = snake.get_head_coordinates();
+
+    let vectors = distances_with_vectors(distances).map(|(vector, _)| vector);
+
+    for (dx, dy) in vectors {
+        let mut apple = 0.0;
+        let mut body = 0.0;
+        let mut position = Position::new(head.x + dx, head.y + dy);
+        let mut steps = 1;
+
+        while board.contains(position) {
+            if apple == 0.0 && food.get_position() == position {
+                apple = 1.0 / steps as f64;
+            }
+
+            if body == 0.0 && snake.is_in_position(position) {
+                body = 1.0 / steps as f64;
+            }
+
+            position = Position::new(position.x + dx, position.y + dy);
+            steps += 1;
+        }
+
+        let distance_info = ray_by_vector(distances, (dx, dy));
+        distance_info.distance_to_apple = apple;
+        distance_info.distance_to_body = body;
+    }
+}
+
+pub fn validate_network_input(input: &[f64], expected_size: usize) -> Result<(), InputSizeMismatch> {
+    if input.len() != expected_size {
+        return Err(InputSizeMismatch {expected: expected_size, actual: input.len()})
+    }
+
+    Ok(())
+}
+
+// Fills the input in the order of SENSOR_LAYOUT, distances can be built by hand to test the sensors without a board
+pub fn build_input_from_distances(distances: &Distances, head_direction: Direction, tail_direction: Direction) -> Vec<f64> {
+    let mut input = Vec::with_capacity(FIRST_LAYER_SIZE);
+
+    add_distance_to_input(distances.top, &mut input, MAX_Y_DISTANCE);
+    add_distance_to_input(distances.right, &mut input, MAX_X_DISTANCE);
+    add_distance_to_input(distances.bottom, &mut input, MAX_Y_DISTANCE);
+    add_distance_to_input(distances.left, &mut input, MAX_X_DISTANCE);
+
+    add_distance_to_input(distances.top_right, &mut input, *MAX_DISTANCE);
+    add_distance_to_input(distances.bottom_right, &mut input, *MAX_DISTANCE);
+    add_distance_to_input(distances.bottom_left, &mut input, *MAX_DISTANCE);
+    add_distance_to_input(distances.top_left, &mut input, *MAX_DISTANCE);
+
+    add_direction_to_input(head_direction, &mut input);
+    add_direction_to_input(tail_direction, &mut input);
+
+    debug_assert_eq!(input.len(), SENSOR_LAYOUT.len());
+
+    input
+}
+
+fn add_direction_to_input(direction: Direction, input: &mut Vec<f64>) {
+    match direction {
+        Direction::UP => input.append(&mut vec![1.0, 0.0, 0.0, 0.0]),
+        Direction::RIGHT => input.append(&mut vec![0.0, 1.0, 0.0, 0.0]),
+        Direction::DOWN => input.append(&mut vec![0.0, 0.0, 1.0, 0.0]),
+        Direction::LEFT => input.append(&mut vec![0.0, 0.0, 0.0, 1.0]),
+    }
+}
+
+pub fn standardize_input(input: &mut [f64]) {
+    for (value, (mean, standard_deviation)) in input.iter_mut().zip(INPUT_STATISTICS.iter()) {
+        *value = (*value - mean) / standard_deviation;
+    }
+}
+
+fn calculate_input_statistics() -> Vec<(f64, f64)> {
+    let mut statistics = vec![(0.0, 1.0); FIRST_LAYER_SIZE];
+
+    // Only the wall distance depends on the head position, the snake and food don't matter
+    let inputs = (0..GRID_SIZE.1)
+        .flat_map(|y| (0..GRID_SIZE.0).map(move |x| Position::new(x, y)))
+        .map(|position| generate_network_input(&Snake::new(position), &Food::new(position)))
+        .collect::<Vec<_>>();
+
+    for i in (0..24).step_by(3) {
+        let mean = inputs.iter().map(|input| input[i]).sum::<f64>() / inputs.len() as f64;
+        let variance = inputs.iter().map(|input| (input[i] - mean).powi(2)).sum::<f64>() / inputs.len() as f64;
+
+        statistics[i] = (mean, variance.sqrt().max(f64::EPSILON));
+    }
+
+    // One-hot over 4 equally likely directions
+    for statistic in statistics.iter_mut().skip(24) {
+        *statistic = (0.25, (0.25_f64 * 0.75).sqrt());
+    }
+
+    statistics
+}
+
+fn add_distance_to_input(distance: DistanceInfo, input: &mut Vec<f64>, max: f64) {
+    input.push(distance.distance_to_wall / max);
+    input.push(distance.distance_to_apple);
+    input.push(distance.distance_to_body);
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Move {
+    FORWARD,
+    LEFT,
+    RIGHT
+}
+
+pub fn interpret_network_output(output: &Vec<f64>) -> Result<Direction, String> {
+    if output.is_empty() {
+        return Err("Network output is empty".to_string())
+    }
+
+    // Starts from the first output, not 0, so all negative outputs (e.g. after a Linear layer) are compared too
+    let mut max = output[0];
+    let mut index = 0;
+
+    for (i, val) in output.iter().enumerate() {
+        if *val > max {
+            max = *val;
+            index = i;
+        }
+    }
+
+    Ok(direction_for_output_index(index))
+}
+
+pub fn sample_network_output<R: Rng>(output: &[f64], rng: &mut R) -> Result<Direction, String> {
+    let distribution = WeightedIndex::new(output)
+        .map_err(|error| format!("Couldn't sample from network output {:?}: {}", output, error))?;
+
+    Ok(direction_for_output_index(distribution.sample(rng)))
+}
+
+pub fn direction_for_output_index(index: usize) -> Direction {
+    if index == 0 {
+        Direction::UP
+    } else if index == 1 {
+        Direction::RIGHT
+    } else if index == 2 {
+        Direction::DOWN
+    } else {
+        Direction::LEFT
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use std::sync::mpsc;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::ai::brain::Brain;
+    use crate::ai::controller::{Controller, OracleController};
+    use crate::ai::genetic_algorithm::{Evaluation, EvaluationKey, PopulationOptions};
+    use crate::ai::neural_network_utils::{Function, Linear, NeuralNetworkOptions, ReLU, Softmax};
+    use rayon::prelude::*;
+    use crate::ai::neural_network::NeuralNetwork;
+    use crate::ai::test_helpers::{single_layer_options, small_options};
+    use crate::ai::snake_trainer::{build_input_from_distances, calculate_fitness, champion_island, evaluate, evaluate_game, evaluate_game_breakdown, evaluate_with, evaluate_with_apples, record_evaluation_game, EvalContext, food_spawner_for, game_rng_for, spawn_position, spawn_snake, SpawnMode, generate_network_input, generate_new_food_on_board,
+                                   interpret_network_output, max_apples, max_possible_fitness, sample_network_output, standardize_input, EvaluationSettings, FitnessWeights, MLSnakeOptions, SurvivalDecay,
+                                   SnakeTrainer, FIRST_LAYER_SIZE, SENSOR_LAYOUT};
+    use crate::snake::food_spawner::FoodSpawner;
+    use crate::snake::game_simulation::{GameSimulation, MAX_STEPS_WITHOUT_APPLE};
+    use crate::snake::snake_game::{Board, Direction, Distances, Food, Position, Snake};
+    use crate::visualisation::game_constants::{GRID_SIZE, MAX_DISTANCE, MAX_X_DISTANCE, MAX_Y_DISTANCE};
+
+    // Returns the first four sensor readings as they are
+    struct PassthroughBrain;
+
+    impl Brain for PassthroughBrain {
+        fn from_weights(_weights: Vec<f64>, _options: NeuralNetworkOptions) -> Result<Self, String> {
+            Ok(PassthroughBrain)
+        }
+
+        fn input_size(&self) -> usize {
+            FIRST_LAYER_SIZE
+        }
+
+        fn output_size(&self) -> usize {
+            4
+        }
+
+        fn forward(&self, input: &[f64]) -> Vec<f64> {
+            input[..4].to_vec()
+        }
+
+        fn update_weights(&mut self, _weights: Vec<f64>) {}
+    }
+
+    #[test]
+    pub fn evaluate_should_accept_custom_brain() {
+        //given
+        let options = single_layer_options();
+
+        //when
+        let fitness = evaluate_with::<PassthroughBrain, _>(&[], &options, &EvaluationSettings::default(), &mut rand::thread_rng());
+
+        //then
+        assert!(fitness.is_finite() && fitness > 0.0, "Game played by custom brain should be evaluated");
+    }
+
+    // Plays the given directions, then keeps going in the last one
+    struct ScriptedController {
+        directions: VecDeque<Direction>,
+        last_direction: Direction
+    }
+
+    impl Controller for ScriptedController {
+        fn decide(&mut self, _snake: &Snake, _food: &Food) -> Direction {
+            if let Some(direction) = self.directions.pop_front() {
+                self.last_direction = direction;
+            }
+
+            self.last_direction
+        }
+    }
+
+    struct ScriptedFoodSpawner {
+        positions: VecDeque<Position>
+    }
+
+    impl FoodSpawner for ScriptedFoodSpawner {
+        fn spawn(&mut self, _snake: &Snake) -> Food {
+            Food::new(self.positions.pop_front().unwrap())
+        }
+    }
+
+    // Keeps the food on the snake's own board
+    struct BoardFoodSpawner {
+        rng: StdRng
+    }
+
+    impl FoodSpawner for BoardFoodSpawner {
+        fn spawn(&mut self, snake: &Snake) -> Food {
+            generate_new_food_on_board(snake, &snake.get_board(), &mut self.rng).unwrap()
+        }
+    }
+
+    #[test]
+    pub fn max_possible_fitness_should_bound_every_game_on_small_board() {
+        //given
+        let board = Board::new(5, 5);
+        let fitness_weights = FitnessWeights::default()
+            .with_length_milestone_bonus(10.0)
+            .with_coverage_weight(1.0)
+            .with_occupancy_bonus(0.5, 100.0);
+
+        let new_simulation = |seed: u64| {
+            let mut food_spawner = BoardFoodSpawner {rng: StdRng::seed_from_u64(seed)};
+            let snake = Snake::try_new(Position::new(2, 2), board).unwrap();
+            let food = food_spawner.spawn(&snake);
+
+            GameSimulation::with_food_spawner(snake, food, Box::new(food_spawner))
+        };
+
+        //when
+        let max_fitness = max_possible_fitness(board, &fitness_weights);
+
+        let mut fitnesses: Vec<f64> = (0..20)
+            .map(|seed| evaluate_game(&mut OracleController, new_simulation(seed), &fitness_weights))
+            .collect();
+
+        // Circles without eating until the timeout
+        let mut circling_controller = ScriptedController {
+            directions: [Direction::UP, Direction::RIGHT, Direction::DOWN, Direction::LEFT].repeat(MAX_STEPS_WITHOUT_APPLE as usize).into(),
+            last_direction: Direction::LEFT
+        };
+        fitnesses.push(evaluate_game(&mut circling_controller, new_simulation(0), &fitness_weights));
+
+        //then
+        assert!(max_fitness.is_finite(), "Maximum fitness should be finite, got {max_fitness}");
+        assert!(fitnesses.iter().all(|fitness| *fitness < max_fitness),
+                "Every game should score below {max_fitness}, got {:?}", fitnesses);
+    }
+
+    #[test]
+    pub fn max_apples_should_leave_out_starting_snake() {
+        //when-then
+        assert_eq!(max_apples(Board::default()), (GRID_SIZE.0 * GRID_SIZE.1) as u32 - 2, "Every cell but the starting snake can hold an apple");
+        assert_eq!(max_apples(Board::new(5, 5)), 23, "5x5 board should fit 23 apples");
+    }
+
+    #[test]
+    pub fn evaluate_game_should_score_scripted_game() {
+        //given
+        // Eats at (7, 5) and (7, 7) in 4 steps, then goes down into the wall on step 7
+        let mut controller = ScriptedController {
+            directions: VecDeque::from(vec![Direction::RIGHT, Direction::RIGHT, Direction::DOWN, Direction::DOWN]),
+            last_direction: Direction::RIGHT
+        };
+        let food_spawner = ScriptedFoodSpawner {
+            positions: VecDeque::from(vec![Position::new(7, 7), Position::new(0, 0)])
+        };
+        let simulation = GameSimulation::with_food_spawner(Snake::new(Position::new(5, 5)),
+                                                           Food::new(Position::new(7, 5)), Box::new(food_spawner));
+
+        let (steps, score) = (7.0_f64, 2.0_f64);
+        let expected_fitness = steps + 2.0_f64.powf(score) + 500.0 * score.powf(2.1) - score.powf(1.2) * (0.25 * steps).powf(1.3);
+
+        //when
+        let fitness = evaluate_game(&mut controller, simulation, &FitnessWeights::default());
+
+        //then
+        assert!((fitness - expected_fitness).abs() < 1e-9, "Fitness should be {expected_fitness}, got {fitness}");
+    }
+
+    #[test]
+    pub fn fitness_breakdown_should_add_up_to_total() {
+        //given
+        let fitness_weights = FitnessWeights::default().with_coverage_weight(2.0).with_wall_hugging_penalty(1.0);
+
+        //when
+        let (mut controller, simulation) = scripted_game();
+        let breakdown = evaluate_game_breakdown(&mut controller, simulation, &fitness_weights);
+
+        let (mut controller, simulation) = scripted_game();
+        let fitness = evaluate_game(&mut controller, simulation, &fitness_weights);
+
+        //then
+        let sum = breakdown.survival_term + breakdown.apple_term - breakdown.penalty_term + breakdown.bonus_term;
+
+        assert!((sum - breakdown.total).abs() < 1e-9, "Terms should add up to {}, got {}", breakdown.total, sum);
+        assert_eq!(breakdown.total, fitness, "Total should be the evaluated fitness");
+        assert_eq!(breakdown.apples, 2, "Both scripted apples should be counted");
+        assert_eq!(breakdown.survival_term, 7.0, "Survival term should be the steps");
+        assert_eq!(breakdown.apple_term, 2.0_f64.powf(2.0) + 500.0 * 2.0_f64.powf(2.1), "Apple term should follow the score");
+        assert_eq!(breakdown.bonus_term, 2.0 * 7.0, "Every visited cell should be rewarded, the snake never slides along a wall");
+    }
+
+    #[test]
+    pub fn fitness_breakdown_should_clamp_bonus_with_negative_base_once() {
+        //given
+        // Survival is punished, so the terms without the bonus add up below 0
+        let fitness_weights = FitnessWeights::new(-100.0, 0.0);
+
+        //when
+        let (mut controller, simulation) = scripted_game();
+        let small_bonus = evaluate_game_breakdown(&mut controller, simulation, &fitness_weights.with_coverage_weight(2.0));
+
+        let (mut controller, simulation) = scripted_game();
+        let large_bonus = evaluate_game_breakdown(&mut controller, simulation, &fitness_weights.with_coverage_weight(200.0));
+
+        //then
+        let large_sum = large_bonus.survival_term + large_bonus.apple_term - large_bonus.penalty_term + large_bonus.bonus_term;
+
+        assert_eq!(small_bonus.total, 0.0, "Bonus smaller than the negative base shouldn't raise the total");
+        assert!(large_sum > 0.0 && (large_sum - large_bonus.total).abs() < 1e-9,
+                "Bonus should be added to the negative base, expected {}, got {}", large_sum, large_bonus.total);
+    }
+
+    // Same game as in evaluate_game_should_score_scripted_game
+    fn scripted_game() -> (ScriptedController, GameSimulation) {
+        let controller = ScriptedController {
+            directions: VecDeque::from(vec![Direction::RIGHT, Direction::RIGHT, Direction::DOWN, Direction::DOWN]),
+            last_direction: Direction::RIGHT
+        };
+        let food_spawner = ScriptedFoodSpawner {
+            positions: VecDeque::from(vec![Position::new(7, 7), Position::new(0, 0)])
+        };
+        let simulation = GameSimulation::with_food_spawner(Snake::new(Position::new(5, 5)),
+                                                           Food::new(Position::new(7, 5)), Box::new(food_spawner));
+
+        (controller, simulation)
+    }
+
+    #[test]
+    pub fn wall_hugging_penalty_should_grow_with_steps_along_wall() {
+        //given
+        // Slides along the top wall from (1, 0) until it hits the right wall, 8 steps along the wall
+        let scripted_game = || {
+            let controller = ScriptedController {directions: VecDeque::new(), last_direction: Direction::RIGHT};
+            let food_spawner = ScriptedFoodSpawner {positions: VecDeque::new()};
+            let simulation = GameSimulation::with_food_spawner(Snake::new(Position::new(1, 0)),
+                                                               Food::new(Position::new(5, 5)), Box::new(food_spawner));
+            (controller, simulation)
+        };
+
+        //when
+        let (mut controller, simulation) = scripted_game();
+        let fitness_without_penalty = evaluate_game(&mut controller, simulation, &FitnessWeights::default());
+
+        let (mut controller, simulation) = scripted_game();
+        let fitness_with_penalty = evaluate_game(&mut controller, simulation,
+                                                 &FitnessWeights::default().with_wall_hugging_penalty(0.5));
+
+        //then
+        assert_eq!(fitness_without_penalty - fitness_with_penalty, 0.5 * 8.0,
+                   "Penalty should be proportional to the steps along the wall");
+    }
+
+    #[test]
+    pub fn spawn_snake_should_face_every_direction_with_body_behind_head() {
+        //given
+        let evaluation_settings = EvaluationSettings {random_facing: true, ..EvaluationSettings::default()};
+        let mut rng = StdRng::seed_from_u64(11);
+
+        //when
+        let snakes = (0..100)
+            .map(|_| spawn_snake(&evaluation_settings, &mut rng))
+            .collect::<Vec<_>>();
+
+        //then
+        for facing in [Direction::UP, Direction::LEFT, Direction::RIGHT, Direction::DOWN] {
+            assert!(snakes.iter().any(|snake| snake.get_current_direction() == facing),
+                    "Every facing should occur over many spawns");
+        }
+
+        for snake in &snakes {
+            let positions = snake.get_positions();
+            let mut behind_head = positions[0];
+            behind_head.make_a_move(snake.get_current_direction().inverse());
+
+            assert_eq!(positions.len(), 2, "Snake should start with the head and one body segment");
+            assert_eq!(positions[1], behind_head, "Body should be right behind the head");
+        }
+
+        assert!(small_options(1).with_random_facing(true).with_spawn_mode(SpawnMode::Fixed(Position::new(3, 0))).validate().is_err(),
+                "Fixed spawn by the wall should be rejected when the facing is random");
+    }
+
+    #[test]
+    pub fn spawn_position_should_follow_spawn_mode() {
+        //given
+        let mut rng = StdRng::seed_from_u64(3);
+
+        //when
+        let center = spawn_position(SpawnMode::Center, &mut rng);
+        let fixed = spawn_position(SpawnMode::Fixed(Position::new(2, 7)), &mut rng);
+        let random = spawn_position(SpawnMode::Random, &mut rng);
+
+        //then
+        assert_eq!(center, Position::new(GRID_SIZE.0 / 2, GRID_SIZE.1 / 2), "Head should spawn in the center of the board");
+        assert_eq!(fixed, Position::new(2, 7), "Head should spawn at the given position");
+        assert!((2..GRID_SIZE.0 - 2).contains(&random.x) && (2..GRID_SIZE.1 - 2).contains(&random.y),
+                "Random spawn should keep its distance from the walls");
+        assert!(small_options(1).with_spawn_mode(SpawnMode::Fixed(Position::new(0, 3))).validate().is_err(),
+                "Fixed spawn that leaves the body off the board should be rejected");
+    }
+
+    #[test]
+    pub fn pinned_first_food_should_be_shared_by_every_individual() {
+        //given
+        let options = small_options(1).with_pinned_first_food(Position::new(0, 0));
+        let snake = Snake::new(Position::new(5, 5));
+        let mut rng = StdRng::seed_from_u64(5);
+
+        //when
+        let foods: Vec<_> = (0..10)
+            .map(|_| {
+                let mut food_spawner = food_spawner_for(&options.evaluation_settings, &mut rng);
+                (food_spawner.spawn(&snake).get_position(), food_spawner.spawn(&snake).get_position())
+            })
+            .collect();
+
+        //then
+        assert!(foods.iter().all(|(first, _)| *first == Position::new(0, 0)), "Every individual should get the pinned first apple");
+        assert!(foods.iter().any(|(_, second)| *second != foods[0].1), "Later apples should still vary between individuals");
+        assert!(small_options(1).with_pinned_first_food(Position::new(-1, 0)).validate().is_err(),
+                "First food off the board should be rejected");
+    }
+
+    #[test]
+    pub fn length_milestone_bonus_should_be_added_for_every_new_length() {
+        //given
+        // Eats at (7, 5) and (7, 7), growing to length 3 and 4, then goes down into the wall
+        let scripted_game = || {
+            let controller = ScriptedController {
+                directions: VecDeque::from(vec![Direction::RIGHT, Direction::RIGHT, Direction::DOWN, Direction::DOWN]),
+                last_direction: Direction::RIGHT
+            };
+            let food_spawner = ScriptedFoodSpawner {
+                positions: VecDeque::from(vec![Position::new(7, 7), Position::new(0, 0)])
+            };
+            let simulation = GameSimulation::with_food_spawner(Snake::new(Position::new(5, 5)),
+                                                               Food::new(Position::new(7, 5)), Box::new(food_spawner));
+            (controller, simulation)
+        };
+
+        //when
+        let (mut controller, simulation) = scripted_game();
+        let fitness_without_bonus = evaluate_game(&mut controller, simulation, &FitnessWeights::default());
+
+        let (mut controller, simulation) = scripted_game();
+        let fitness_with_bonus = evaluate_game(&mut controller, simulation,
+                                               &FitnessWeights::default().with_length_milestone_bonus(100.0));
+
+        //then
+        assert_eq!(fitness_with_bonus - fitness_without_bonus, 100.0 * 2.0,
+                   "Bonus should be applied once for every new maximum length");
+    }
+
+    #[test]
+    pub fn coverage_weight_should_reward_exploring_more_cells() {
+        //given
+        // Both loops time out without an apple, the first one circles 4 cells and the second one 8 cells
+        let looping_game = |cycle: &[Direction]| {
+            let controller = ScriptedController {
+                directions: cycle.iter().cycle().take(MAX_STEPS_WITHOUT_APPLE as usize).copied().collect(),
+                last_direction: Direction::RIGHT
+            };
+            let food_spawner = ScriptedFoodSpawner {positions: VecDeque::new()};
+            let simulation = GameSimulation::with_food_spawner(Snake::new(Position::new(5, 5)),
+                                                               Food::new(Position::new(0, 0)), Box::new(food_spawner));
+            (controller, simulation)
+        };
+        let small_loop = [Direction::RIGHT, Direction::DOWN, Direction::LEFT, Direction::UP];
+        let big_loop = [Direction::RIGHT, Direction::RIGHT, Direction::DOWN, Direction::DOWN,
+                        Direction::LEFT, Direction::LEFT, Direction::UP, Direction::UP];
+        let fitness_weights = FitnessWeights::default().with_coverage_weight(10.0);
+
+        //when
+        let (mut controller, simulation) = looping_game(&small_loop);
+        let small_loop_fitness = evaluate_game(&mut controller, simulation, &FitnessWeights::default());
+        let (mut controller, simulation) = looping_game(&big_loop);
+        let big_loop_fitness = evaluate_game(&mut controller, simulation, &FitnessWeights::default());
+
+        let (mut controller, simulation) = looping_game(&small_loop);
+        let small_loop_coverage_fitness = evaluate_game(&mut controller, simulation, &fitness_weights);
+        let (mut controller, simulation) = looping_game(&big_loop);
+        let big_loop_coverage_fitness = evaluate_game(&mut controller, simulation, &fitness_weights);
+
+        //then
+        assert_eq!(small_loop_fitness, big_loop_fitness, "Games with the same score and steps should score the same");
+        assert_eq!(big_loop_coverage_fitness - small_loop_coverage_fitness, 10.0 * 4.0,
+                   "Bigger loop should be rewarded for the extra visited cells");
+    }
+
+    #[test]
+    pub fn occupancy_bonus_should_be_applied_once_after_reaching_threshold() {
+        //given
+        // On a 4x2 board the snake eats at (2, 0) and (3, 0) reaching half of the board, then eats at (2, 1) and goes left into the wall
+        let scripted_game = || {
+            let controller = ScriptedController {
+                directions: VecDeque::from(vec![Direction::RIGHT, Direction::RIGHT, Direction::DOWN, Direction::LEFT]),
+                last_direction: Direction::RIGHT
+            };
+            let food_spawner = ScriptedFoodSpawner {
+                positions: VecDeque::from(vec![Position::new(3, 0), Position::new(2, 1), Position::new(0, 0)])
+            };
+            let snake = Snake::try_new(Position::new(1, 0), Board::new(4, 2)).unwrap();
+            let simulation = GameSimulation::with_food_spawner(snake, Food::new(Position::new(2, 0)), Box::new(food_spawner));
+            (controller, simulation)
+        };
+
+        //when
+        let (mut controller, simulation) = scripted_game();
+        let fitness_without_bonus = evaluate_game(&mut controller, simulation, &FitnessWeights::default());
+
+        let (mut controller, simulation) = scripted_game();
+        let fitness_with_bonus = evaluate_game(&mut controller, simulation,
+                                               &FitnessWeights::default().with_occupancy_bonus(0.5, 1000.0));
+
+        //then
+        assert_eq!(fitness_with_bonus - fitness_without_bonus, 1000.0, "Bonus should be applied exactly once");
+        assert!(small_options(1).with_fitness_weights(FitnessWeights::default().with_occupancy_bonus(1.5, 1000.0))
+                    .validate().is_err(), "Threshold above the whole board should be rejected");
+    }
+
+    #[test]
+    pub fn network_input_should_follow_sensor_layout() {
+        //given
+        // Snake::new faces right with its tail pointing right
+        let snake = Snake::new(Position::new(5, 5));
+        let food = Food::new(Position::new(0, 0));
+
+        //when
+        let input = generate_network_input(&snake, &food);
+
+        //then
+        assert_eq!(input.len(), SENSOR_LAYOUT.len(), "Input should have a value for every sensor");
+        assert_eq!(SENSOR_LAYOUT[24..], ["head_up", "head_right", "head_down", "head_left",
+                                         "tail_up", "tail_right", "tail_down", "tail_left"],
+                   "Direction one-hots should take the last 8 slots");
+        assert_eq!(input[24..], [0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0], "Head and tail should both point right");
+    }
+
+    #[test]
+    pub fn standardized_input_should_have_zero_mean_wall_distances() {
+        //given
+        let food = Food::new(Position::new(8, 2));
+        let mut inputs = (0..GRID_SIZE.1)
+            .flat_map(|y| (0..GRID_SIZE.0).map(move |x| Position::new(x, y)))
+            .map(|position| generate_network_input(&Snake::new(position), &food))
+            .collect::<Vec<_>>();
+
+        //when
+        inputs.iter_mut().for_each(|input| standardize_input(input));
+
+        //then
+        let wall_distances = inputs.iter()
+            .flat_map(|input| input.iter().take(24).step_by(3).copied())
+            .collect::<Vec<_>>();
+        let mean = wall_distances.iter().sum::<f64>() / wall_distances.len() as f64;
+
+        assert!(mean.abs() < 1e-9, "Mean of wall distances should be close to 0, got {mean}");
+        assert!(inputs.iter().all(|input| input[1] == 0.0 || input[1] == 1.0), "Apple flags shouldn't change");
+    }
+
+    // Snake taking 3 cells of a 2x2 board, (0, 1) is the only free one
+    fn snake_on_almost_full_board() -> Snake {
+        let mut snake = Snake::new(Position::new(1, 0));
+
+        snake.move_in_dir(Direction::DOWN);
+        snake.update_state(&Food::new(Position::new(1, 1)));
+
+        snake
+    }
+
+    #[test]
+    pub fn generate_new_food_on_board_should_use_only_free_cell() {
+        //given
+        let snake = snake_on_almost_full_board();
+        let board = Board::new(2, 2);
+
+        //when
+        let food = generate_new_food_on_board(&snake, &board, &mut rand::thread_rng());
+
+        //then
+        assert_eq!(food.map(|food| food.get_position()), Some(Position::new(0, 1)), "Food should be placed in the only free cell");
+    }
+
+    #[test]
+    pub fn generate_new_food_on_board_should_return_none_on_full_board() {
+        //given
+        let mut snake = snake_on_almost_full_board();
+        snake.move_in_dir(Direction::LEFT);
+        snake.update_state(&Food::new(Position::new(0, 1)));
+
+        let board = Board::new(2, 2);
+
+        //when
+        let food = generate_new_food_on_board(&snake, &board, &mut rand::thread_rng());
+
+        //then
+        assert_eq!(snake.get_length(), 4, "Snake should take the whole board");
+        assert!(food.is_none(), "There should be no place for the food");
+    }
+
+    #[test]
+    pub fn population_should_continue_after_train_headless() {
+        //given
+        let options = small_options(2);
+        let mut population = SnakeTrainer::train_headless(&options);
+        let generation_after_training = population.get_generation();
+
+        //when
+        let stats = population.continue_for(2, evaluate, &options.eval_context());
+
+        //then
+        assert_eq!(generation_after_training, 2, "Training should run 2 generations");
+        assert_eq!(stats.iter().map(|generation_stats| generation_stats.generation).collect::<Vec<_>>(), vec![3, 4],
+                   "Evolution should continue from generation 3");
+        assert_eq!(population.get_generation(), 4, "Population should advance 4 generations in total");
+    }
+
+    #[test]
+    pub fn sample_network_output_should_follow_probabilities() {
+        //given
+        let output = vec![0.1, 0.2, 0.3, 0.4];
+        let mut rng = StdRng::seed_from_u64(42);
+        let n_of_draws = 10000;
+
+        //when
+        let mut counts = [0; 4];
+
+        for _ in 0..n_of_draws {
+            match sample_network_output(&output, &mut rng).unwrap() {
+                Direction::UP => counts[0] += 1,
+                Direction::RIGHT => counts[1] += 1,
+                Direction::DOWN => counts[2] += 1,
+                Direction::LEFT => counts[3] += 1
+            }
+        }
+
+        //then
+        counts.iter()
+            .zip(output.iter())
+            .for_each(|(count, probability)| {
+                let frequency = *count as f64 / n_of_draws as f64;
+                assert!((frequency - probability).abs() < 0.02, "Frequency {frequency} should be close to {probability}");
+            });
+    }
+
+    #[test]
+    pub fn evaluation_should_count_only_weights_clamped_by_its_own_network() {
+        //given
+        let genome: Vec<f64> = (0..FIRST_LAYER_SIZE * 4).map(|i| if i % 4 == 0 { 3.0 } else { 0.1 }).collect();
+        let context_with_cap = |max_abs_weight: f64| {
+            let population_options = PopulationOptions::new(10, FIRST_LAYER_SIZE * 4, -1.0, 1.0, 0.9, 0.3, 0.3, 1).unwrap();
+
+            MLSnakeOptions::new(population_options, single_layer_options().with_max_abs_weight(max_abs_weight)).eval_context()
+        };
+        let (loose_context, tight_context) = (context_with_cap(5.0), context_with_cap(1.0));
+
+        //when
+        let counts: Vec<_> = (0..16).into_par_iter()
+            .map(|i| {
+                let eval_context = if i % 2 == 0 { &loose_context } else { &tight_context };
+
+                evaluate_with_apples(&genome, eval_context, EvaluationKey {generation: 0, index: i}).clamped_weights
+            })
+            .collect();
+
+        //then
+        assert!(counts.iter().step_by(2).all(|count| *count == 0), "No weight is above the loose cap, got {:?}", counts);
+        assert!(counts.iter().skip(1).step_by(2).all(|count| *count == FIRST_LAYER_SIZE),
+                "Every fourth weight is above the tight cap, got {:?}", counts);
+    }
+
+    #[test]
+    pub fn recorded_evaluation_game_should_end_with_evaluated_apples() {
+        //given
+        let options = small_options(1).with_evaluation_seed(11);
+        let genome: Vec<f64> = (0..options.neural_network_options.weight_count()).map(|i| (i as f64 * 0.7).sin()).collect();
+        let eval_context = options.eval_context();
+        let key = EvaluationKey {generation: 2, index: 5};
+
+        //when
+        let frames = record_evaluation_game(&genome, &eval_context, key);
+
+        //then
+        assert_eq!(frames.last().map(|frame| frame.score), Some(evaluate_with_apples(&genome, &eval_context, key).apples),
+                   "Recorded game should be the one the genome was evaluated on");
+    }
+
+    #[test]
+    pub fn recorded_champion_game_should_end_with_best_apples_of_its_generation() {
+        //given
+        let options = small_options(3);
+        let eval_context = options.eval_context();
+        let (populations, evaluations) = SnakeTrainer::train_best_genomes(options, &eval_context);
+
+        //when-then
+        for (genome, evaluation) in populations.iter().zip(&evaluations) {
+            let frames = record_evaluation_game(genome, &eval_context, evaluation.key);
+
+            assert_eq!(frames.last().map(|frame| frame.score), Some(evaluation.apples),
+                       "Replay of generation {} champion should end with its apples", evaluation.key.generation);
+        }
+    }
+
+    #[test]
+    pub fn unseeded_evaluation_should_replay_same_game_for_same_key_on_many_threads() {
+        //given
+        let options = small_options(1).with_evaluation_threads(4);
+        let genomes = (0..8)
+            .map(|i| vec![i as f64 * 0.125 - 0.5; options.neural_network_options.weight_count()])
+            .collect::<Vec<_>>();
+        let eval_context = options.eval_context();
+        let evaluate_all = || options.evaluation_pool().install(|| {
+            genomes.par_iter()
+                .enumerate()
+                .map(|(index, genome)| evaluate(genome, &eval_context, EvaluationKey {generation: 1, index}))
+                .collect::<Vec<_>>()
+        });
+
+        //when
+        let first_fitness = evaluate_all();
+        let second_fitness = evaluate_all();
+
+        //then
+        assert_eq!(first_fitness, second_fitness, "Without a seed the run's random seed should still fix every game");
+    }
+
+    #[test]
+    pub fn seeded_evaluation_should_match_serial_baseline_on_many_threads() {
+        //given
+        let options = small_options(1).with_evaluation_seed(7).with_evaluation_threads(4);
+        let genomes = (0..8)
+            .map(|i| vec![i as f64 * 0.125 - 0.5; options.neural_network_options.weight_count()])
+            .collect::<Vec<_>>();
+        let eval_context = options.eval_context();
+        let key = |index| EvaluationKey {generation: 3, index};
+
+        //when
+        let parallel_fitness = options.evaluation_pool().install(|| {
+            genomes.par_iter()
+                .enumerate()
+                .map(|(index, genome)| evaluate(genome, &eval_context, key(index)))
+                .collect::<Vec<_>>()
+        });
+
+        let serial_fitness = genomes.iter()
+            .enumerate()
+            .map(|(index, genome)| evaluate_with::<NeuralNetwork, _>(genome, &options.neural_network_options,
+                                                                     &options.evaluation_settings, &mut game_rng_for(7, key(index))))
+            .collect::<Vec<_>>();
+
+        //then
+        assert_eq!(parallel_fitness, serial_fitness, "Fitness should only depend on the seed and the evaluated individual");
+    }
+
+    #[test]
+    pub fn seeded_training_should_be_reproducible_on_many_threads() {
+        //given
+        let seeded_options = |threads: usize| {
+            let options = small_options(3);
+
+            MLSnakeOptions::new(options.genetic_algorithm_options.with_seed(7), options.neural_network_options)
+                .with_evaluation_seed(7)
+                .with_evaluation_threads(threads)
+        };
+
+        //when
+        let first = SnakeTrainer::train_headless(&seeded_options(4));
+        let second = SnakeTrainer::train_headless(&seeded_options(4));
+        let serial = SnakeTrainer::train_headless(&seeded_options(1));
+
+        //then
+        assert_eq!(first.get_best_chromosomes(), second.get_best_chromosomes(), "Seeded runs should evolve the same genomes");
+        assert_eq!(first.get_best_score(), second.get_best_score(), "Seeded runs should end with the same best score");
+        assert_eq!(first.get_best_chromosomes(), serial.get_best_chromosomes(), "Number of threads shouldn't change the result");
+    }
+
+    #[test]
+    pub fn evaluate_should_read_fitness_weights_from_eval_context() {
+        //given
+        // With no weight on survival the apple term is at least 1 even without eating, since 2^0 = 1
+        let options = small_options(1);
+        let genome = vec![0.5; options.neural_network_options.weight_count()];
+        let mut no_reward_context = EvalContext::new(options.neural_network_options.clone());
+        no_reward_context.evaluation_settings.fitness_weights = FitnessWeights::new(0.0, 0.0);
+        let mut apple_context = no_reward_context.clone();
+        apple_context.evaluation_settings.fitness_weights = FitnessWeights::new(0.0, 1.0);
+
+        //when
+        let key = EvaluationKey {generation: 0, index: 0};
+        let no_reward_fitness = evaluate(&genome, &no_reward_context, key);
+        let apple_fitness = evaluate(&genome, &apple_context, key);
+
+        //then
+        assert_eq!(no_reward_fitness, 0.0, "Nothing should be rewarded with both weights at 0");
+        assert!(apple_fitness >= 1.0, "Apple weight from the context should be used, got {}", apple_fitness);
+    }
+
+    #[test]
+    pub fn higher_apple_weight_should_favour_apples_over_survival() {
+        //given
+        let (apple_steps, apple_score) = (150.0, 1.0);
+        let (survival_steps, survival_score) = (1000.0, 0.0);
+
+        let default_weights = FitnessWeights::default();
+        let apple_weights = FitnessWeights::new(1.0, 3.0);
+
+        //when-then
+        assert!(calculate_fitness(survival_steps, survival_score, &default_weights) >
+                    calculate_fitness(apple_steps, apple_score, &default_weights),
+                "Long game without apples should win with default weights");
+        assert!(calculate_fitness(apple_steps, apple_score, &apple_weights) >
+                    calculate_fitness(survival_steps, survival_score, &apple_weights),
+                "Short game with an apple should win with higher apple weight");
+    }
+
+    #[test]
+    pub fn survival_decay_should_let_apples_beat_stalling() {
+        //given
+        let (stalling_steps, stalling_score) = (10000.0, 0.0);
+        let (apple_steps, apple_score) = (300.0, 3.0);
+
+        let linear_weights = FitnessWeights::default();
+        let logarithmic_weights = FitnessWeights::default().with_survival_decay(SurvivalDecay::Logarithmic);
+        let capped_weights = FitnessWeights::default().with_survival_decay(SurvivalDecay::Capped(500.0));
+
+        //when-then
+        assert!(calculate_fitness(stalling_steps, stalling_score, &linear_weights) >
+                    calculate_fitness(apple_steps, apple_score, &linear_weights),
+                "Stalling should win while survival is linear");
+
+        for weights in [logarithmic_weights, capped_weights] {
+            assert!(calculate_fitness(apple_steps, apple_score, &weights) >
+                        calculate_fitness(stalling_steps, stalling_score, &weights),
+                    "Eating should win with {:?}", weights.survival_decay);
+        }
+
+        assert!(small_options(1).with_fitness_weights(FitnessWeights::default().with_survival_decay(SurvivalDecay::Capped(0.0)))
+                    .validate().is_err(), "Cap of 0 should be rejected");
+    }
+
+    #[test]
+    pub fn builder_defaults_should_produce_valid_options() {
+        //when
+        let options = MLSnakeOptions::builder().build();
+        let tweaked_options = MLSnakeOptions::builder().population_size(50).build();
+
+        //then
+        let options = options.unwrap_or_else(|error| panic!("Default options should be valid: {}", error));
+
+        assert!(options.validate().is_ok());
+        assert_eq!(options.neural_network_options.layers_sizes_vec, vec![FIRST_LAYER_SIZE as u16, 20, 12, 4],
+                   "Default network should be the standard architecture");
+        assert_eq!(options.genetic_algorithm_options.number_of_chromosomes, options.neural_network_options.weight_count(),
+                   "Every weight should have a chromosome");
+        assert!(tweaked_options.is_ok(), "Changing a single field should keep the options valid");
+        assert!(MLSnakeOptions::builder().mutation_prob(2.0).build().is_err(), "Invalid values should still be rejected");
+    }
+
+    #[test]
+    pub fn validate_should_return_error_on_mismatched_chromosomes() {
+        //given
+        let population_options = PopulationOptions::new(10, 5, -1.0, 1.0, 0.9, 0.3, 0.3, 1).unwrap();
+        let neural_network_options = NeuralNetworkOptions::new(
+            vec![FIRST_LAYER_SIZE as u16, 4, 4],
+            vec![Box::new(ReLU), Box::new(Softmax)]
+        );
+
+        //when-then
+        assert!(small_options(1).validate().is_ok(), "Matching options should be valid");
+        assert!(MLSnakeOptions::new(population_options, neural_network_options).validate().is_err(),
+                "There should be an error");
+    }
+
+    #[test]
+    pub fn train_best_genomes_should_keep_only_last_genomes() {
+        //given
+        let keep_last = 3;
+        let options = small_options(6).with_keep_last(keep_last);
+
+        //when
+        let (populations, evaluations) = SnakeTrainer::train_best_genomes(options.clone(), &options.eval_context());
+
+        //then
+        assert_eq!(populations.len(), keep_last, "Only {} genomes should be kept", keep_last);
+        assert_eq!(evaluations.len(), keep_last, "Every kept genome should keep its evaluation");
+    }
+
+    #[test]
+    pub fn train_best_genomes_should_keep_every_generation_by_default() {
+        //given
+        let options = small_options(4);
+
+        //when
+        let (populations, evaluations) = SnakeTrainer::train_best_genomes(options.clone(), &options.eval_context());
+
+        //then
+        assert_eq!(populations.len(), 4, "Every generation's best genome should be kept");
+        assert_eq!(evaluations.len(), 4, "Every generation's best genome should keep its evaluation");
+    }
+
+    #[test]
+    pub fn train_with_sender_should_send_one_genome_per_generation_in_order() {
+        //given
+        let n_of_generations = 5;
+        let options = small_options(n_of_generations);
+        let (sender, receiver) = mpsc::channel();
+
+        //when
+        SnakeTrainer::train_with_sender(options, sender);
+
+        //then
+        let received: Vec<(u64, Evaluation, Vec<f64>)> = receiver.iter().collect();
+
+        assert_eq!(received.len(), n_of_generations as usize,
+                   "There should be one genome per generation");
+
+        received.iter()
+            .enumerate()
+            .for_each(|(i, (generation, _evaluation, chromosomes))| {
+                assert_eq!(*generation, i as u64 + 1, "Genomes should arrive in generation order");
+                assert_eq!(chromosomes.len(), FIRST_LAYER_SIZE * 4 + 4 * 4,
+                           "Every genome should have all of the chromosomes");
+            });
+    }
+
+    #[test]
+    pub fn interpret_network_output_should_return_error_on_empty_output() {
+        //when-then
+        assert!(interpret_network_output(&vec![]).is_err(), "There should be an error");
+    }
+
+    #[test]
+    pub fn interpret_network_output_should_pick_highest_output() {
+        //when
+        let direction = interpret_network_output(&vec![0.1, 0.2, 0.6, 0.1]);
+
+        //then
+        assert!(direction == Ok(Direction::DOWN), "Direction should be DOWN");
+    }
+
+    #[test]
+    pub fn interpret_network_output_should_pick_highest_of_all_negative_outputs() {
+        //given
+        let mut output = vec![-3.0, -2.5, -0.5, -1.0];
+        Linear.apply(&mut output);
+
+        //when
+        let direction = interpret_network_output(&output);
+
+        //then
+        assert!(direction == Ok(Direction::DOWN), "The least negative output should be picked");
+    }
+
+    #[test]
+    pub fn build_input_from_distances_should_fill_every_sensor_slot() {
+        //given
+        let distances = Distances {
+            top: (MAX_Y_DISTANCE / 2.0, 1.0, 0.0).into(),
+            right: (MAX_X_DISTANCE, 0.0, 1.0).into(),
+            bottom: (0.0, 0.5, 0.25).into(),
+            left: (MAX_X_DISTANCE / 4.0, 0.0, 0.0).into(),
+            top_right: (*MAX_DISTANCE, 1.0, 1.0).into(),
+            bottom_right: (*MAX_DISTANCE / 2.0, 0.0, 0.0).into(),
+            bottom_left: (0.0, 0.0, 1.0).into(),
+            top_left: (*MAX_DISTANCE / 4.0, 1.0, 0.0).into()
+        };
+
+        //when
+        let input = build_input_from_distances(&distances, Direction::RIGHT, Direction::DOWN);
+
+        //then
+        let expected = vec![
+            0.5, 1.0, 0.0,
+            1.0, 0.0, 1.0,
+            0.0, 0.5, 0.25,
+            0.25, 0.0, 0.0,
+            1.0, 1.0, 1.0,
+            0.5, 0.0, 0.0,
+            0.0, 0.0, 1.0,
+            0.25, 1.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0
+        ];
+
+        assert_eq!(input.len(), SENSOR_LAYOUT.len(), "Input should have a value for every sensor");
+
+        input.iter()
+            .zip(expected.iter())
+            .zip(SENSOR_LAYOUT.iter())
+            .for_each(|((actual, expected), sensor)| {
+                assert!((actual - expected).abs() < 1e-9, "Sensor {} should be {}, but was {}", sensor, expected, actual);
+            });
+    }
+
+    #[test]
+    pub fn champion_island_should_have_best_final_score_across_islands() {
+        //given
+        let options = small_options(2);
+        let islands = SnakeTrainer::train_islands(&options, 2);
+
+        let best_scores: Vec<_> = islands.iter().map(|island| island.population.get_best_score()).collect();
+        let best_chromosomes: Vec<_> = islands.iter().map(|island| island.population.get_best_chromosomes()).collect();
+
+        //when
+        let champion = champion_island(islands);
+
+        //then
+        let best_score = best_scores.iter().copied().fold(f64::MIN, f64::max);
+        let expected_index = best_scores.iter().position(|score| *score == best_score).unwrap();
+
+        assert_eq!(champion.best_genomes.len(), 2, "Champion should have the best genome of every generation");
+        assert_eq!(champion.population.get_best_score(), best_score, "Champion should have the best score of all islands");
+        assert_eq!(champion.best_genomes.last(), Some(&best_chromosomes[expected_index]),
+                   "Last genome of the trace should be the best genome of the champion's final generation");
+    }
+}
@@ -0,0 +1,29 @@
+use crate::ai::genetic_algorithm::PopulationOptions;
+use crate::ai::neural_network_utils::{NeuralNetworkOptions, ReLU, Softmax};
+use crate::ai::snake_trainer::{MLSnakeOptions, FIRST_LAYER_SIZE};
+
+// A population of 10 with one small hidden layer, quick enough to train in a test
+pub fn small_options(n_of_generations: u64) -> MLSnakeOptions {
+    let population_options = PopulationOptions::new(
+        10,
+        FIRST_LAYER_SIZE * 4 + 4 * 4,
+        -1.0,
+        1.0,
+        0.9,
+        0.3,
+        0.3,
+        n_of_generations
+    ).unwrap();
+
+    let neural_network_options = NeuralNetworkOptions::new(
+        vec![FIRST_LAYER_SIZE as u16, 4, 4],
+        vec![Box::new(ReLU), Box::new(Softmax)]
+    );
+
+    MLSnakeOptions::new(population_options, neural_network_options)
+}
+
+// Inputs straight to the 4 moves, genomes have FIRST_LAYER_SIZE * 4 weights
+pub fn single_layer_options() -> NeuralNetworkOptions {
+    NeuralNetworkOptions::new(vec![FIRST_LAYER_SIZE as u16, 4], vec![Box::new(Softmax)])
+}
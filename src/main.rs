@@ -1,27 +1,40 @@
-use crate::ai::genetic_algorithm::PopulationOptions;
-use crate::ai::neural_network_utils::{NeuralNetworkOptions, ReLU, Softmax};
-use crate::ai::snake_trainer::{MLSnakeOptions, SnakeTrainer, FIRST_LAYER_SIZE};
+use std::path::Path;
+use crate::ai::controller::OracleController;
+use crate::ai::snake_trainer::{MLSnakeOptions, SnakeTrainer, BEST_NETWORK_PATH, BEST_REPLAY_PATH};
+use crate::visualisation::game_constants::{FPS, GRID_GAMES};
+use crate::visualisation::ml_game::{play_game_with_controller, PlaybackMode};
+use crate::visualisation::replay::play_replay;
 
 mod visualisation;
 mod ai;
 mod snake;
 
 fn main() {
-    let population_options = PopulationOptions::new(
-        500,
-        FIRST_LAYER_SIZE * 20 + 20 * 12 + 12 * 4,
-        -1.0,
-        1.0,
-        0.9,
-        0.3,
-        0.3,
-        2000
-    );
+    let args: Vec<String> = std::env::args().collect();
+    let options = MLSnakeOptions::builder().build().unwrap();
 
-    let neural_network_options = NeuralNetworkOptions::new(
-        vec![FIRST_LAYER_SIZE as u16, 20, 12, 4],
-        vec![Box::new(ReLU), Box::new(ReLU), Box::new(Softmax)]
-    );
+    let playback_mode = if args.iter().any(|arg| arg == "--best-loop") {
+        Some(PlaybackMode::SingleBestLoop)
+    } else if let Some(position) = args.iter().position(|arg| arg == "--grid") {
+        let count = args.get(position + 1).and_then(|count| count.parse().ok()).unwrap_or(GRID_GAMES);
 
-    SnakeTrainer::train(MLSnakeOptions::new(population_options, neural_network_options));
+        Some(PlaybackMode::Grid(count))
+    } else {
+        None
+    };
+
+    if let Some(position) = args.iter().position(|arg| arg == "--play-replay") {
+        // Without a path the game recorded by the last training is played
+        let path = args.get(position + 1).map_or(BEST_REPLAY_PATH, String::as_str);
+
+        play_replay(Path::new(path)).unwrap();
+    } else if args.iter().any(|arg| arg == "--oracle") {
+        play_game_with_controller(Box::new(OracleController), FPS).unwrap();
+    } else if args.iter().any(|arg| arg == "--live") {
+        SnakeTrainer::train_live(options);
+    } else if args.iter().any(|arg| arg == "--replay") {
+        SnakeTrainer::replay(Path::new(BEST_NETWORK_PATH), options, playback_mode);
+    } else {
+        SnakeTrainer::train(options, playback_mode);
+    }
 }
@@ -0,0 +1,126 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::ai::snake_trainer::{generate_new_food, generate_new_food_on_board};
+use crate::snake::snake_game::{Food, Position, Snake};
+
+pub trait FoodSpawner {
+    fn spawn(&mut self, snake: &Snake) -> Food;
+}
+
+pub struct RandomFoodSpawner;
+
+impl FoodSpawner for RandomFoodSpawner {
+    fn spawn(&mut self, snake: &Snake) -> Food {
+        generate_new_food(snake).unwrap_or_else(|| panic!("There is no free cell left for the food"))
+    }
+}
+
+pub struct SeededFoodSpawner {
+    rng: StdRng
+}
+
+impl SeededFoodSpawner {
+    pub fn new(seed: u64) -> Self {
+        SeededFoodSpawner {rng: StdRng::seed_from_u64(seed)}
+    }
+
+    pub fn from_entropy() -> Self {
+        SeededFoodSpawner {rng: StdRng::from_entropy()}
+    }
+}
+
+impl FoodSpawner for SeededFoodSpawner {
+    fn spawn(&mut self, snake: &Snake) -> Food {
+        generate_new_food_on_board(snake, &snake.get_board(), &mut self.rng)
+            .unwrap_or_else(|| panic!("There is no free cell left for the food"))
+    }
+}
+
+// The first food goes to a fixed position if it's free, every next one comes from `spawner`
+pub struct PinnedFirstFoodSpawner {
+    first_food: Option<Position>,
+    spawner: Box<dyn FoodSpawner>
+}
+
+impl PinnedFirstFoodSpawner {
+    pub fn new(first_food: Position, spawner: Box<dyn FoodSpawner>) -> Self {
+        PinnedFirstFoodSpawner {first_food: Some(first_food), spawner}
+    }
+}
+
+impl FoodSpawner for PinnedFirstFoodSpawner {
+    fn spawn(&mut self, snake: &Snake) -> Food {
+        match self.first_food.take() {
+            Some(position) if !snake.is_in_position(position) => Food::new(position),
+            _ => self.spawner.spawn(snake)
+        }
+    }
+}
+
+// Places food in the free cell farthest from the snake's head
+pub struct AdversarialFoodSpawner;
+
+impl FoodSpawner for AdversarialFoodSpawner {
+    fn spawn(&mut self, snake: &Snake) -> Food {
+        let head = snake.get_head_coordinates();
+        let board = snake.get_board();
+
+        let position = (0..board.height)
+            .flat_map(|y| (0..board.width).map(move |x| Position::new(x, y)))
+            .filter(|position| !snake.is_in_position(*position))
+            .max_by(|a, b| a.get_distance_from_pos(&head).total_cmp(&b.get_distance_from_pos(&head)))
+            .unwrap_or_else(|| panic!("There is no free cell left for the food"));
+
+        Food::new(position)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::snake::food_spawner::{AdversarialFoodSpawner, FoodSpawner, SeededFoodSpawner};
+    use crate::snake::snake_game::{Board, Position, Snake};
+    use crate::visualisation::game_constants::GRID_SIZE;
+
+    #[test]
+    pub fn adversarial_spawner_should_place_food_in_farthest_free_cell() {
+        //given
+        let snake = Snake::new(Position::new(2, 2));
+
+        //when
+        let food = AdversarialFoodSpawner.spawn(&snake);
+
+        //then
+        assert_eq!(food.get_position(), Position::new(GRID_SIZE.0 - 1, GRID_SIZE.1 - 1),
+                   "Food should be placed in the corner farthest from the head");
+    }
+
+    #[test]
+    pub fn adversarial_spawner_should_skip_cells_taken_by_snake() {
+        //given
+        let snake = Snake::new(Position::new(GRID_SIZE.0 - 1, GRID_SIZE.1 - 1));
+
+        //when
+        let food = AdversarialFoodSpawner.spawn(&snake);
+
+        //then
+        assert_eq!(food.get_position(), Position::new(0, 0),
+                   "Food should be placed in the corner farthest from the head");
+        assert!(!snake.is_in_position(food.get_position()), "Food shouldn't be placed on the snake");
+    }
+
+    #[test]
+    pub fn spawners_should_place_food_on_snake_board() {
+        //given
+        let board = Board::new(4, 3);
+        let snake = Snake::try_new(Position::new(1, 0), board).unwrap();
+        let mut seeded_spawner = SeededFoodSpawner::new(3);
+
+        //when
+        let farthest = AdversarialFoodSpawner.spawn(&snake);
+        let seeded = (0..20).map(|_| seeded_spawner.spawn(&snake)).collect::<Vec<_>>();
+
+        //then
+        assert_eq!(farthest.get_position(), Position::new(3, 2), "Farthest cell should be the corner of the snake's board");
+        assert!(seeded.iter().all(|food| board.contains(food.get_position())), "Seeded food should stay on the snake's board");
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::HashSet;
+use crate::snake::food_spawner::{FoodSpawner, RandomFoodSpawner};
+use crate::snake::snake_game::{Ate, Direction, Food, Position, Snake};
+
+pub const MAX_STEPS_WITHOUT_APPLE: u32 = 150;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum EndReason {
+    HitWall,
+    HitSelf,
+    Timeout,
+    Won,
+    Starved
+}
+
+pub struct GameSimulation {
+    snake: Snake,
+    food: Food,
+    steps: u32,
+    steps_without_apple: u32,
+    score: u32,
+    end_reason: Option<EndReason>,
+    food_spawner: Box<dyn FoodSpawner>,
+    wall_hugging_steps: u32,
+    was_next_to_wall: bool,
+    max_length: usize,
+    length_milestones: u32,
+    visited_cells: HashSet<Position>
+}
+
+impl GameSimulation {
+    pub fn new(snake: Snake, food: Food) -> Self {
+        GameSimulation::with_food_spawner(snake, food, Box::new(RandomFoodSpawner))
+    }
+
+    pub fn with_food_spawner(snake: Snake, food: Food, food_spawner: Box<dyn FoodSpawner>) -> Self {
+        let was_next_to_wall = snake.get_board().is_next_to_wall(snake.get_head_coordinates());
+        let max_length = snake.get_length();
+        let visited_cells = HashSet::from([snake.get_head_coordinates()]);
+
+        GameSimulation {
+            snake,
+            food,
+            steps: 0,
+            steps_without_apple: 0,
+            score: 0,
+            end_reason: None,
+            food_spawner,
+            wall_hugging_steps: 0,
+            was_next_to_wall,
+            max_length,
+            length_milestones: 0,
+            visited_cells
+        }
+    }
+
+    pub fn step(&mut self, direction: Direction) -> Option<EndReason> {
+        if self.end_reason.is_some() {
+            return self.end_reason
+        }
+
+        self.steps += 1;
+        self.steps_without_apple += 1;
+
+        self.snake.move_in_dir(direction);
+
+        let score_before = self.score;
+
+        self.end_reason = advance_game(&mut self.snake, &mut self.food, self.food_spawner.as_mut(), &mut self.score);
+
+        if self.score > score_before {
+            self.steps_without_apple = 0;
+        }
+
+        let board = self.snake.get_board();
+        let head = self.snake.get_head_coordinates();
+
+        if board.contains(head) {
+            self.visited_cells.insert(head);
+        }
+
+        let is_next_to_wall = board.is_next_to_wall(head);
+
+        if is_next_to_wall && self.was_next_to_wall {
+            self.wall_hugging_steps += 1;
+        }
+
+        self.was_next_to_wall = is_next_to_wall;
+
+        if self.snake.get_length() > self.max_length {
+            self.max_length = self.snake.get_length();
+            self.length_milestones += 1;
+        }
+
+        if self.end_reason.is_none() && self.steps_without_apple >= MAX_STEPS_WITHOUT_APPLE {
+            self.end_reason = Some(EndReason::Timeout);
+        }
+
+        self.end_reason
+    }
+
+    pub fn get_snake(&self) -> &Snake {
+        &self.snake
+    }
+
+    pub fn get_food(&self) -> &Food {
+        &self.food
+    }
+
+    pub fn get_steps(&self) -> u32 {
+        self.steps
+    }
+
+    pub fn get_score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn get_end_reason(&self) -> Option<EndReason> {
+        self.end_reason
+    }
+
+    // Steps moving along a wall, i.e. the head was next to a wall before and after the step
+    pub fn get_wall_hugging_steps(&self) -> u32 {
+        self.wall_hugging_steps
+    }
+
+    // How many times the snake reached a new maximum length
+    pub fn get_length_milestones(&self) -> u32 {
+        self.length_milestones
+    }
+
+    pub fn get_max_length(&self) -> usize {
+        self.max_length
+    }
+
+    // Largest fraction of the snake's board it has taken up so far
+    pub fn get_max_occupancy(&self) -> f64 {
+        let board = self.snake.get_board();
+
+        self.max_length as f64 / (board.width as f64 * board.height as f64)
+    }
+
+    // Distinct cells the head has been on, including the starting one
+    pub fn get_visited_cells(&self) -> usize {
+        self.visited_cells.len()
+    }
+
+    // Countdown to the timeout, reset every time an apple is eaten
+    pub fn get_remaining_steps_without_apple(&self) -> u32 {
+        MAX_STEPS_WITHOUT_APPLE.saturating_sub(self.steps_without_apple)
+    }
+}
+
+// A single game tick shared by the headless simulation and both games. The snake's direction has to be set beforehand.
+pub fn advance_game(snake: &mut Snake, food: &mut Food, food_spawner: &mut dyn FoodSpawner, score: &mut u32) -> Option<EndReason> {
+    snake.update_state(food);
+
+    match snake.get_ate() {
+        Some(Ate::Food) => {
+            *score += 1;
+
+            let board = snake.get_board();
+
+            if snake.get_length() >= (board.width * board.height) as usize {
+                Some(EndReason::Won)
+            } else {
+                *food = food_spawner.spawn(snake);
+                None
+            }
+        },
+        Some(Ate::Border) => Some(EndReason::HitWall),
+        Some(Ate::Itself) => Some(EndReason::HitSelf),
+        Some(Ate::Starved) => Some(EndReason::Starved),
+        None => None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::snake::food_spawner::RandomFoodSpawner;
+    use crate::snake::game_simulation::{advance_game, EndReason, GameSimulation, MAX_STEPS_WITHOUT_APPLE};
+    use crate::snake::snake_game::{Board, Direction, Food, Position, Snake};
+    use crate::visualisation::game_constants::GRID_SIZE;
+
+    fn play(simulation: &mut GameSimulation, directions: &[Direction]) -> Option<EndReason> {
+        let mut end_reason = None;
+
+        for direction in directions {
+            end_reason = simulation.step(*direction);
+        }
+
+        end_reason
+    }
+
+    #[test]
+    pub fn advance_game_should_eat_food_and_spawn_new_one() {
+        //given
+        let mut snake = Snake::new(Position::new(4, 4));
+        let mut food = Food::new(Position::new(5, 4));
+        let mut score = 0;
+
+        //when
+        let end_reason = advance_game(&mut snake, &mut food, &mut RandomFoodSpawner, &mut score);
+
+        //then
+        assert_eq!(end_reason, None, "Game shouldn't end");
+        assert_eq!(score, 1, "Score should be incremented");
+        assert_eq!(snake.get_length(), 3, "Snake should grow");
+        assert_ne!(food.get_position(), Position::new(5, 4), "New food should be spawned");
+        assert!(!snake.is_in_position(food.get_position()), "New food shouldn't be on the snake");
+    }
+
+    #[test]
+    pub fn advance_game_should_return_hit_wall() {
+        //given
+        let mut snake = Snake::new(Position::new(5, 0));
+        let mut food = Food::new(Position::new(9, 9));
+        let mut score = 0;
+
+        snake.move_in_dir(Direction::UP);
+
+        //when
+        let end_reason = advance_game(&mut snake, &mut food, &mut RandomFoodSpawner, &mut score);
+
+        //then
+        assert_eq!(end_reason, Some(EndReason::HitWall), "Snake should hit the wall");
+        assert_eq!(score, 0, "Score shouldn't change");
+    }
+
+    #[test]
+    pub fn step_should_end_with_hit_wall() {
+        //given
+        let snake = Snake::new(Position::new(5, 2));
+        let mut simulation = GameSimulation::new(snake, Food::new(Position::new(9, 9)));
+
+        //when
+        let end_reason = play(&mut simulation, &[Direction::UP; 3]);
+
+        //then
+        assert_eq!(end_reason, Some(EndReason::HitWall), "Snake should hit the top wall");
+        assert_eq!(simulation.get_steps(), 3, "Game should end on the third step");
+    }
+
+    #[test]
+    pub fn step_should_end_with_hit_self() {
+        //given
+        let snake = Snake::new(Position::new(2, 5));
+        let mut simulation = GameSimulation::new(snake, Food::new(Position::new(3, 5)));
+
+        //when
+        for x in 4..=6 {
+            simulation.step(Direction::RIGHT);
+            simulation.food = Food::new(Position::new(x, 5));
+        }
+        simulation.food = Food::new(Position::new(0, 0));
+
+        let end_reason = play(&mut simulation, &[Direction::DOWN, Direction::LEFT, Direction::UP]);
+
+        //then
+        assert_eq!(simulation.get_score(), 3, "Snake should eat three apples");
+        assert_eq!(end_reason, Some(EndReason::HitSelf), "Snake should bite itself");
+    }
+
+    #[test]
+    pub fn step_should_end_with_timeout() {
+        //given
+        let snake = Snake::new(Position::new(5, 5));
+        let mut simulation = GameSimulation::new(snake, Food::new(Position::new(0, 0)));
+
+        let circle = [Direction::DOWN, Direction::LEFT, Direction::UP, Direction::RIGHT];
+
+        //when
+        let end_reason = play(&mut simulation, &circle.repeat(MAX_STEPS_WITHOUT_APPLE as usize / 4 + 1));
+
+        //then
+        assert_eq!(end_reason, Some(EndReason::Timeout), "Snake should run out of steps");
+        assert_eq!(simulation.get_steps(), MAX_STEPS_WITHOUT_APPLE, "Game should end after max steps without apple");
+    }
+
+    #[test]
+    pub fn wall_hugging_steps_should_only_count_steps_along_wall() {
+        //given
+        let snake = Snake::new(Position::new(1, 0));
+        let mut simulation = GameSimulation::new(snake, Food::new(Position::new(0, 5)));
+
+        let along_wall = [Direction::RIGHT; 5];
+        let to_center = [Direction::DOWN; 3];
+
+        //when
+        play(&mut simulation, &along_wall);
+        let steps_along_wall = simulation.get_wall_hugging_steps();
+
+        play(&mut simulation, &to_center);
+
+        //then
+        assert_eq!(steps_along_wall, 5, "Every step along the top wall should count");
+        assert_eq!(simulation.get_wall_hugging_steps(), 5, "Steps away from the wall shouldn't count");
+    }
+
+    #[test]
+    pub fn wall_hugging_steps_should_use_snakes_board() {
+        //given
+        let board = Board::new(5, 5);
+        let snake = Snake::try_new(Position::new(1, 4), board).unwrap();
+        let mut simulation = GameSimulation::new(snake, Food::new(Position::new(0, 0)));
+
+        //when
+        play(&mut simulation, &[Direction::RIGHT; 3]);
+
+        //then
+        assert_eq!(simulation.get_wall_hugging_steps(), 3, "Bottom row of the small board should be next to the wall");
+        assert_eq!(simulation.get_visited_cells(), 4, "Every cell of the small board the head entered should count");
+    }
+
+    #[test]
+    pub fn remaining_steps_without_apple_should_count_down_to_timeout() {
+        //given
+        let snake = Snake::new(Position::new(5, 5));
+        let mut simulation = GameSimulation::new(snake, Food::new(Position::new(0, 0)));
+
+        let circle = [Direction::DOWN, Direction::LEFT, Direction::UP, Direction::RIGHT];
+
+        //when-then
+        assert_eq!(simulation.get_remaining_steps_without_apple(), MAX_STEPS_WITHOUT_APPLE, "Countdown should start full");
+
+        for (i, direction) in circle.iter().cycle().take(MAX_STEPS_WITHOUT_APPLE as usize).enumerate() {
+            assert_eq!(simulation.get_end_reason(), None, "Game shouldn't end before the countdown reaches 0");
+
+            simulation.step(*direction);
+
+            assert_eq!(simulation.get_remaining_steps_without_apple(), MAX_STEPS_WITHOUT_APPLE - i as u32 - 1,
+                       "Countdown should decrement every step");
+        }
+
+        assert_eq!(simulation.get_end_reason(), Some(EndReason::Timeout), "Game should end once the countdown reaches 0");
+    }
+
+    #[test]
+    pub fn step_should_end_with_won() {
+        //given
+        let snake = Snake::new(Position::new(1, 0));
+        let mut simulation = GameSimulation::new(snake, Food::new(Position::new(2, 0)));
+
+        let mut path = Vec::new();
+
+        for y in 0..GRID_SIZE.1 {
+            let row: Vec<i16> = if y % 2 == 0 {
+                (0..GRID_SIZE.0).collect()
+            } else {
+                (0..GRID_SIZE.0).rev().collect()
+            };
+
+            row.into_iter().for_each(|x| path.push(Position::new(x, y)));
+        }
+
+        //when
+        let mut end_reason = None;
+
+        for next in path.windows(2).skip(1) {
+            let direction = if next[1].y > next[0].y {
+                Direction::DOWN
+            } else if next[1].x > next[0].x {
+                Direction::RIGHT
+            } else {
+                Direction::LEFT
+            };
+
+            simulation.food = Food::new(next[1]);
+            end_reason = simulation.step(direction);
+        }
+
+        //then
+        assert_eq!(end_reason, Some(EndReason::Won), "Snake should fill the whole board");
+        assert_eq!(simulation.get_snake().get_length(), (GRID_SIZE.0 * GRID_SIZE.1) as usize,
+                   "Snake should take every cell");
+    }
+}
@@ -1 +1,4 @@
-pub mod snake_game;
\ No newline at end of file
+pub mod snake_game;
+pub mod game_simulation;
+pub mod food_spawner;
+pub mod symmetry;
\ No newline at end of file
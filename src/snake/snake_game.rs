@@ -4,12 +4,12 @@ use ggez::graphics::{Canvas, Color, DrawParam, Quad, Rect};
 use ggez::input::keyboard::{KeyCode};
 use once_cell::sync::Lazy;
 use crate::ai::snake_trainer::Move;
-use crate::visualisation::game_constants::{GRID_CELL_SIZE,GRID_SIZE};
+use crate::visualisation::game_constants::{CELL_STYLE, GRID_CELL_SIZE, GRID_SIZE};
 
 static SIN_45: Lazy<f64> = Lazy::new(|| FRAC_PI_4.sin());
 static COS_45: Lazy<f64> = Lazy::new(|| FRAC_PI_4.cos());
 
-#[derive(Copy, PartialEq, Clone, Debug)]
+#[derive(Copy, PartialEq, Eq, Hash, Clone, Debug)]
 pub struct Position {
     pub(crate) x: i16,
     pub(crate) y: i16
@@ -40,11 +40,55 @@ impl Position {
 
 impl From<Position> for Rect {
     fn from(value: Position) -> Self {
-        Rect::new_i32(
-            (value.x * GRID_CELL_SIZE.0) as i32,
-            (value.y * GRID_CELL_SIZE.1) as i32,
-            GRID_CELL_SIZE.0 as i32,
-            GRID_CELL_SIZE.1 as i32)
+        scaled_rect(value, GRID_CELL_SIZE)
+    }
+}
+
+// Pixel rect of a board cell drawn with the given cell size, the game logic only sees the position
+pub fn scaled_rect(position: Position, cell_size: (f32, f32)) -> Rect {
+    Rect::new(position.x as f32 * cell_size.0, position.y as f32 * cell_size.1, cell_size.0, cell_size.1)
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum CellStyle {
+    Filled,
+    // Only the border of the cell with the given thickness
+    Outlined(f32),
+    // Filled, leaving the given gap on every side so neighbouring cells don't touch
+    Inset(f32)
+}
+
+impl CellStyle {
+    pub fn cell_rect(&self, position: Position) -> Rect {
+        let rect: Rect = position.into();
+
+        match self {
+            CellStyle::Inset(gap) => Rect::new(rect.x + gap, rect.y + gap, rect.w - 2.0 * gap, rect.h - 2.0 * gap),
+            _ => rect
+        }
+    }
+
+    fn draw_cell(&self, canvas: &mut Canvas, position: Position, color: Color) {
+        let rect = self.cell_rect(position);
+
+        let rects = match self {
+            CellStyle::Outlined(thickness) => vec![
+                Rect::new(rect.x, rect.y, rect.w, *thickness),
+                Rect::new(rect.x, rect.y + rect.h - thickness, rect.w, *thickness),
+                Rect::new(rect.x, rect.y, *thickness, rect.h),
+                Rect::new(rect.x + rect.w - thickness, rect.y, *thickness, rect.h)
+            ],
+            _ => vec![rect]
+        };
+
+        for rect in rects {
+            canvas.draw(
+                &Quad,
+                DrawParam::new()
+                    .dest_rect(rect)
+                    .color(color)
+            );
+        }
     }
 }
 
@@ -54,6 +98,33 @@ impl From<(i16,i16)> for Position {
     }
 }
 
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Board {
+    pub width: i16,
+    pub height: i16
+}
+
+impl Board {
+    pub fn new(width: i16, height: i16) -> Self {
+        Board {width, height}
+    }
+
+    pub fn contains(&self, position: Position) -> bool {
+        position.x >= 0 && position.y >= 0 && position.x < self.width && position.y < self.height
+    }
+
+    pub fn is_next_to_wall(&self, position: Position) -> bool {
+        self.contains(position) &&
+            (position.x == 0 || position.y == 0 || position.x == self.width - 1 || position.y == self.height - 1)
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::new(GRID_SIZE.0, GRID_SIZE.1)
+    }
+}
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum Direction {
     UP,
@@ -96,15 +167,11 @@ impl Head {
         }
     }
 
-    pub fn draw(&self, canvas: &mut Canvas) {
-        let color = Color::from_rgb(15, 74, 4);
+    pub fn draw(&self, canvas: &mut Canvas, alpha: f32) {
+        let mut color = Color::from_rgb(15, 74, 4);
+        color.a = alpha;
 
-        canvas.draw(
-            &Quad,
-            DrawParam::new()
-                .dest_rect(self.position.into())
-                .color(color)
-        );
+        CELL_STYLE.draw_cell(canvas, self.position, color);
     }
 }
 
@@ -118,15 +185,11 @@ impl Segment {
         Segment {position, direction}
     }
 
-    pub fn draw(&self, canvas: &mut Canvas) {
-        let color = Color::from_rgb(6, 140, 8);
+    pub fn draw(&self, canvas: &mut Canvas, alpha: f32) {
+        let mut color = Color::from_rgb(6, 140, 8);
+        color.a = alpha;
 
-        canvas.draw(
-            &Quad,
-            DrawParam::new()
-                .dest_rect(self.position.into())
-                .color(color)
-        );
+        CELL_STYLE.draw_cell(canvas, self.position, color);
     }
 }
 
@@ -140,14 +203,14 @@ impl Food {
     }
 
     pub fn draw(&self, canvas: &mut Canvas) {
-        let color = Color::from_rgb(255, 0, 0);
+        self.draw_with_alpha(canvas, 1.0)
+    }
 
-        canvas.draw(
-            &Quad,
-            DrawParam::new()
-                .dest_rect(self.position.into())
-                .color(color)
-        );
+    pub fn draw_with_alpha(&self, canvas: &mut Canvas, alpha: f32) {
+        let mut color = Color::from_rgb(255, 0, 0);
+        color.a = alpha;
+
+        CELL_STYLE.draw_cell(canvas, self.position, color);
     }
 
     pub fn get_position(&self) -> Position {
@@ -155,11 +218,13 @@ impl Food {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum Ate {
     Itself,
     Food,
-    Border
+    Border,
+    // Shrunk down to the head alone because of hunger
+    Starved
 }
 
 pub struct Snake {
@@ -167,10 +232,13 @@ pub struct Snake {
     body: VecDeque<Segment>,
     ate: Option<Ate>,
     last_dir: Direction,
-    next_dir: Option<Direction>
+    next_dir: Option<Direction>,
+    board: Board,
+    hunger_interval: Option<u32>,
+    steps_without_food: u32
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct Distances {
     pub(crate) top: DistanceInfo,
     pub(crate) right: DistanceInfo,
@@ -182,7 +250,7 @@ pub struct Distances {
     pub(crate) top_left: DistanceInfo
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct DistanceInfo {
     pub(crate) distance_to_wall: f64,
     pub(crate) distance_to_apple: f64,
@@ -215,15 +283,71 @@ impl From<(f64, f64, f64)> for DistanceInfo {
 
 impl Snake {
     pub fn new(position: Position) -> Self {
+        Snake::new_facing(position, Direction::RIGHT)
+    }
+
+    // The initial body segment is placed right behind the head
+    pub fn new_facing(position: Position, direction: Direction) -> Self {
         let mut body = VecDeque::new();
+        let mut body_position = position;
+        body_position.make_a_move(direction.inverse());
 
-        body.push_back(Segment::new((position.x - 1, position.y).into(), Direction::RIGHT));
+        body.push_back(Segment::new(body_position, direction));
         Snake {
-            head: Head::new(position, Direction::RIGHT),
-            last_dir: Direction::RIGHT,
+            head: Head::new(position, direction),
+            last_dir: direction,
             body,
             ate: None,
-            next_dir: None
+            next_dir: None,
+            board: Board::default(),
+            hunger_interval: None,
+            steps_without_food: 0
+        }
+    }
+
+    // Loses an extra tail segment every `hunger_interval` steps without food and starves once only the head is left
+    pub fn with_hunger(mut self, hunger_interval: u32) -> Self {
+        self.hunger_interval = Some(hunger_interval);
+        self
+    }
+
+    // The initial body segment is placed to the left of the head, so both have to fit on the board
+    pub fn try_new(position: Position, board: Board) -> Result<Self, String> {
+        let body_position = Position::new(position.x - 1, position.y);
+
+        if !board.contains(position) || !board.contains(body_position) {
+            return Err(format!("Snake at ({}, {}) doesn't fit on the board with its body", position.x, position.y))
+        }
+
+        let mut snake = Snake::new(position);
+        snake.board = board;
+
+        Ok(snake)
+    }
+
+    // Rebuilds a snake from its cells ordered from the head to the tail, e.g. a stored replay frame
+    pub fn from_positions(head: Position, body: &[Position]) -> Self {
+        let head_direction = body.first()
+            .map_or(Direction::RIGHT, |first_segment| direction_between(*first_segment, head));
+
+        let mut previous = head;
+        let body = body.iter()
+            .map(|position| {
+                let segment = Segment::new(*position, direction_between(*position, previous));
+                previous = *position;
+                segment
+            })
+            .collect::<VecDeque<_>>();
+
+        Snake {
+            head: Head::new(head, head_direction),
+            last_dir: head_direction,
+            body,
+            ate: None,
+            next_dir: None,
+            board: Board::default(),
+            hunger_interval: None,
+            steps_without_food: 0
         }
     }
 
@@ -241,12 +365,7 @@ impl Snake {
     }
 
     pub fn eats_border(&self) -> bool {
-        match self.head.direction {
-            Direction::LEFT => self.head.position.x < 0,
-            Direction::UP => self.head.position.y < 0,
-            Direction::RIGHT => self.head.position.x >= GRID_SIZE.0,
-            Direction::DOWN => self.head.position.y >= GRID_SIZE.1
-        }
+        !self.board.contains(self.head.position)
     }
 
     pub fn is_in_position(&self, position: Position) -> bool {
@@ -286,24 +405,43 @@ impl Snake {
         match self.ate {
             Some(ate) => {
                 match ate {
-                    Ate::Food => {},
+                    Ate::Food => self.steps_without_food = 0,
                     _ => {self.body.pop_back();}
                 }
             },
             None => {
                 self.body.pop_back();
+                self.apply_hunger();
             }
         }
 
         self.last_dir = self.head.direction;
     }
 
+    fn apply_hunger(&mut self) {
+        self.steps_without_food += 1;
+
+        if let Some(hunger_interval) = self.hunger_interval {
+            if self.steps_without_food.is_multiple_of(hunger_interval) {
+                self.body.pop_back();
+
+                if self.body.is_empty() {
+                    self.ate = Some(Ate::Starved)
+                }
+            }
+        }
+    }
+
     pub fn draw(&self, canvas: &mut Canvas) {
+        self.draw_with_alpha(canvas, 1.0)
+    }
+
+    pub fn draw_with_alpha(&self, canvas: &mut Canvas, alpha: f32) {
         for segment in &self.body {
-            segment.draw(canvas)
+            segment.draw(canvas, alpha)
         }
 
-        self.head.draw(canvas);
+        self.head.draw(canvas, alpha);
     }
 
     pub fn get_ate(&self) -> Option<Ate> {
@@ -319,7 +457,20 @@ impl Snake {
     }
 
     pub fn move_in_dir_with_move(&mut self, move_dir: Move) {
-        let direction = match move_dir {
+        let direction = self.direction_for_move(move_dir);
+
+        self.move_in_dir(direction);
+    }
+
+    // Turning back is ignored by `move_in_dir`, so it counts as going forward
+    pub fn move_for_direction(&self, direction: Direction) -> Move {
+        [Move::LEFT, Move::RIGHT].into_iter()
+            .find(|move_dir| self.direction_for_move(*move_dir) == direction)
+            .unwrap_or(Move::FORWARD)
+    }
+
+    pub fn direction_for_move(&self, move_dir: Move) -> Direction {
+        match move_dir {
             Move::FORWARD => self.head.direction,
             Move::LEFT => match self.head.direction {
                 Direction::UP => Direction::LEFT,
@@ -333,9 +484,29 @@ impl Snake {
                 Direction::DOWN => Direction::LEFT,
                 Direction::LEFT => Direction::UP
             }
-        };
+        }
+    }
 
-        self.move_in_dir(direction);
+    // Checks what a single step would end with, in the same order as `update_state`, without moving the snake
+    pub fn would_die(&self, move_dir: Move, food: &Food, board: &Board) -> Option<Ate> {
+        let mut position = self.head.position;
+        position.make_a_move(self.direction_for_move(move_dir));
+
+        if position == food.position {
+            Some(Ate::Food)
+        } else if !board.contains(position) {
+            Some(Ate::Border)
+        } else if self.body.iter().any(|segment| segment.position == position) {
+            Some(Ate::Itself)
+        } else {
+            None
+        }
+    }
+
+    // FORWARD, LEFT and RIGHT in this order, true when the move doesn't hit a wall or the body
+    pub fn safe_moves(&self, food: &Food, board: &Board) -> [bool; 3] {
+        [Move::FORWARD, Move::LEFT, Move::RIGHT]
+            .map(|move_dir| !matches!(self.would_die(move_dir, food, board), Some(Ate::Border | Ate::Itself)))
     }
 
     pub fn get_distances(&self, food: &Food) -> Distances {
@@ -351,7 +522,7 @@ impl Snake {
             0.0
         };
 
-        let bottom_distance = (GRID_SIZE.1 - self.head.position.y - 1) as f64;
+        let bottom_distance = (self.board.height - self.head.position.y - 1) as f64;
         let bottom_body = self.body.iter()
             .filter(|segment| segment.position.x == self.head.position.x && segment.position.y > self.head.position.y)
             .next().map(|_| 1.0).unwrap_or_else(|| 0.0);
@@ -363,7 +534,7 @@ impl Snake {
             0.0
         };
 
-        let right_distance = (GRID_SIZE.0 - self.head.position.x - 1) as f64;
+        let right_distance = (self.board.width - self.head.position.x - 1) as f64;
         let right_body = self.body.iter()
             .filter(|segment| segment.position.x > self.head.position.x && segment.position.y == self.head.position.y)
             .next().map(|_| 1.0).unwrap_or_else(|| 0.0);
@@ -413,6 +584,71 @@ impl Snake {
         self.head.position
     }
 
+    // Head first, then the body from the neck to the tail
+    pub fn get_positions(&self) -> Vec<Position> {
+        std::iter::once(self.head.position)
+            .chain(self.body.iter().map(|segment| segment.position))
+            .collect()
+    }
+
+    // Top left and bottom right corners of the smallest rectangle holding the head and the whole body
+    pub fn bounding_box(&self) -> (Position, Position) {
+        self.body.iter()
+            .map(|segment| segment.position)
+            .fold((self.head.position, self.head.position), |(min, max), position| (
+                Position::new(min.x.min(position.x), min.y.min(position.y)),
+                Position::new(max.x.max(position.x), max.y.max(position.y))
+            ))
+    }
+
+    pub fn get_board(&self) -> Board {
+        self.board
+    }
+
+    pub fn get_length(&self) -> usize {
+        self.body.len() + 1
+    }
+
+    // Fraction of the board taken by the snake, reaches 1.0 when the game is won
+    pub fn occupancy_ratio(&self, width: i16, height: i16) -> f64 {
+        self.get_length() as f64 / (width as f64 * height as f64)
+    }
+
+    // Length of the shortest path avoiding walls and the current body, None when the food can't be reached.
+    // The body is treated as static, so a path that only opens up as the tail moves isn't found.
+    pub fn safe_path_to_food(&self, food: &Food, board: &Board) -> Option<usize> {
+        self.safe_path_from(self.head.position, food, board)
+    }
+
+    // Same as `safe_path_to_food`, but the path starts at `start` instead of the head
+    pub fn safe_path_from(&self, start: Position, food: &Food, board: &Board) -> Option<usize> {
+        let index = |position: Position| (position.y * board.width + position.x) as usize;
+
+        let mut visited = vec![false; (board.width * board.height) as usize];
+        let mut queue = VecDeque::from(vec![(start, 0)]);
+
+        if board.contains(start) {
+            visited[index(start)] = true;
+        }
+
+        while let Some((position, distance)) = queue.pop_front() {
+            if position == food.position {
+                return Some(distance)
+            }
+
+            for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+                let next = Position::new(position.x + dx, position.y + dy);
+
+                if board.contains(next) && !visited[index(next)] && !self.is_in_position(next) {
+                    visited[index(next)] = true;
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+
+        None
+    }
+
     fn get_distance_in_direction(&self, food_pos: &Position, top_bottom_dist: f64, left_right_dist: f64, vec_sin: f64, vec_cos: f64) -> DistanceInfo {
         let distance;
         if top_bottom_dist < left_right_dist {
@@ -423,10 +659,9 @@ impl Snake {
 
         let apple_vec = Position::new(food_pos.x - self.head.position.x,  self.head.position.y - food_pos.y);
 
-        let apple_vec_len = apple_vec.get_distance();
-
-        let apple = if equal_with_error(apple_vec.x as f64 / apple_vec_len, vec_cos, 0.00001) &&
-            equal_with_error(apple_vec.y as f64 / apple_vec_len, vec_sin, 0.00001) {
+        // On the grid an apple is on a 45° diagonal exactly when both offsets have the same length and the ray's signs
+        let apple = if apple_vec.x != 0 && apple_vec.x.abs() == apple_vec.y.abs() &&
+            apple_vec.x.signum() as f64 == vec_cos.signum() && apple_vec.y.signum() as f64 == vec_sin.signum() {
             1.0
         } else {
             0.0
@@ -456,6 +691,393 @@ impl Snake {
     }
 }
 
+// Direction of a single step from `from` to the neighbouring `to`
+fn direction_between(from: Position, to: Position) -> Direction {
+    if to.x > from.x {
+        Direction::RIGHT
+    } else if to.x < from.x {
+        Direction::LEFT
+    } else if to.y > from.y {
+        Direction::DOWN
+    } else {
+        Direction::UP
+    }
+}
+
 fn equal_with_error(first_value: f64, second_value: f64, error: f64) -> bool {
     return second_value >= first_value - error && second_value <= first_value + error
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use crate::ai::snake_trainer::Move;
+    use ggez::graphics::Rect;
+    use std::f64::consts::SQRT_2;
+    use crate::snake::snake_game::{Ate, Board, CellStyle, Direction, Distances, Food, Head, Position, scaled_rect, Segment, Snake};
+    use crate::visualisation::game_constants::GRID_CELL_SIZE;
+
+    fn snake_from_positions(head: Position, direction: Direction, body: &[Position]) -> Snake {
+        Snake {
+            head: Head::new(head, direction),
+            body: body.iter().map(|position| Segment::new(*position, direction)).collect::<VecDeque<_>>(),
+            ate: None,
+            last_dir: direction,
+            next_dir: None,
+            board: Board::default(),
+            hunger_interval: None,
+            steps_without_food: 0
+        }
+    }
+
+    #[test]
+    pub fn hungry_snake_should_shrink_on_schedule_and_starve() {
+        //given
+        let mut snake = snake_from_positions(Position::new(3, 5), Direction::RIGHT,
+                                             &[Position::new(2, 5), Position::new(1, 5), Position::new(0, 5)])
+            .with_hunger(2);
+        let food = Food::new(Position::new(0, 0));
+
+        //when
+        let lengths: Vec<_> = (0..6)
+            .map(|_| {
+                snake.update_state(&food);
+                (snake.get_length(), snake.get_ate())
+            })
+            .collect();
+
+        //then
+        assert_eq!(lengths, vec![(4, None), (3, None), (3, None), (2, None), (2, None), (1, Some(Ate::Starved))],
+                   "Snake should lose a segment every 2 steps and starve at length 1");
+    }
+
+    #[test]
+    pub fn scaled_rect_should_map_position_to_pixels() {
+        //given
+        let position = Position::new(3, 2);
+
+        //when
+        let rect = scaled_rect(position, (12.5, 30.0));
+
+        //then
+        assert_eq!(rect, Rect::new(37.5, 60.0, 12.5, 30.0), "Cell should be placed and sized by the scale");
+        assert_eq!(Rect::from(position), scaled_rect(position, GRID_CELL_SIZE), "Game should draw with the configured cell size");
+    }
+
+    #[test]
+    pub fn inset_cell_style_should_shrink_rect_by_gap() {
+        //given
+        let position = Position::new(1, 2);
+        let full_rect: Rect = position.into();
+
+        //when
+        let inset_rect = CellStyle::Inset(4.0).cell_rect(position);
+
+        //then
+        assert_eq!(inset_rect, Rect::new(full_rect.x + 4.0, full_rect.y + 4.0, full_rect.w - 8.0, full_rect.h - 8.0),
+                   "Inset cell should leave the gap on every side");
+        assert_eq!(CellStyle::Filled.cell_rect(position), full_rect, "Filled cell should take the whole cell");
+        assert_eq!(CellStyle::Outlined(2.0).cell_rect(position), full_rect, "Outline should go around the whole cell");
+    }
+
+    #[test]
+    pub fn safe_path_to_food_should_go_around_body() {
+        //given
+        // Body blocks column x = 3 except the bottom row, so the food is 4 cells away but the path takes 6 steps
+        let snake = snake_from_positions(Position::new(2, 3), Direction::LEFT,
+                                         &[Position::new(3, 3), Position::new(3, 2), Position::new(3, 1), Position::new(3, 0)]);
+        let food = Food::new(Position::new(4, 1));
+
+        //when
+        let path_length = snake.safe_path_to_food(&food, &Board::new(5, 5));
+
+        //then
+        assert_eq!(path_length, Some(6), "Path should go around the body instead of the Manhattan distance of 4");
+    }
+
+    #[test]
+    pub fn safe_path_to_food_should_return_none_when_food_is_cut_off() {
+        //given
+        let snake = snake_from_positions(Position::new(2, 4), Direction::LEFT,
+                                         &[Position::new(3, 4), Position::new(3, 3), Position::new(3, 2),
+                                           Position::new(3, 1), Position::new(3, 0)]);
+        let food = Food::new(Position::new(4, 2));
+
+        //when
+        let path_length = snake.safe_path_to_food(&food, &Board::new(5, 5));
+
+        //then
+        assert_eq!(path_length, None, "Food behind the body shouldn't be reachable");
+    }
+
+    #[test]
+    pub fn occupancy_ratio_should_divide_length_by_board_size() {
+        //given
+        let snake = snake_from_positions(Position::new(3, 1), Direction::RIGHT,
+                                         &[Position::new(2, 1), Position::new(1, 1), Position::new(0, 1)]);
+
+        //when
+        let ratio = snake.occupancy_ratio(4, 5);
+
+        //then
+        assert_eq!(ratio, 0.2, "Snake of length 4 should take a fifth of a 4x5 board");
+    }
+
+    #[test]
+    pub fn try_new_should_return_error_when_body_is_off_board() {
+        //when
+        let snake = Snake::try_new(Position::new(0, 5), Board::default());
+
+        //then
+        assert!(snake.is_err(), "Body at x = -1 should be rejected");
+    }
+
+    #[test]
+    pub fn get_distances_should_measure_walls_of_snake_board() {
+        //given
+        let snake = Snake::try_new(Position::new(1, 1), Board::new(5, 3)).unwrap();
+        let food = Food::new(Position::new(4, 1));
+
+        //when
+        let distances = snake.get_distances(&food);
+
+        //then
+        assert_eq!(distances.right, (3.0, 1.0, 0.0).into(), "Right wall should be at the edge of the 5 wide board");
+        assert_eq!(distances.bottom, (1.0, 0.0, 0.0).into(), "Bottom wall should be at the edge of the 3 high board");
+    }
+
+    #[test]
+    pub fn try_new_should_place_body_on_board() {
+        //when
+        let snake = Snake::try_new(Position::new(1, 5), Board::default()).unwrap();
+
+        //then
+        assert!(snake.is_in_position(Position::new(0, 5)), "Body should be placed left of the head");
+        assert!(!snake.eats_border(), "Snake should be on the board");
+    }
+
+    #[test]
+    pub fn eats_border_should_check_all_bounds() {
+        //given
+        let snake = snake_from_positions(Position::new(-1, 5), Direction::UP, &[Position::new(-1, 6)]);
+
+        //when
+        let eats_border = snake.eats_border();
+
+        //then
+        assert!(eats_border, "Head outside of the board should be detected regardless of direction");
+    }
+
+    #[test]
+    pub fn would_die_should_only_let_forward_survive() {
+        //given
+        // Wall on the left, body on the right
+        let snake = snake_from_positions(Position::new(0, 5), Direction::UP,
+                                         &[Position::new(0, 6), Position::new(1, 6), Position::new(1, 5)]);
+        let food = Food::new(Position::new(9, 9));
+        let board = Board::default();
+
+        //when-then
+        assert_eq!(snake.would_die(Move::FORWARD, &food, &board), None, "Forward should be safe");
+        assert_eq!(snake.would_die(Move::LEFT, &food, &board), Some(Ate::Border), "Left should hit the wall");
+        assert_eq!(snake.would_die(Move::RIGHT, &food, &board), Some(Ate::Itself), "Right should hit the body");
+        assert_eq!(snake.get_head_coordinates(), Position::new(0, 5), "Snake shouldn't move");
+    }
+
+    #[test]
+    pub fn safe_moves_should_only_allow_move_out_of_corner() {
+        //given
+        // Head in the top left corner facing up, the body blocks the way down
+        let snake = snake_from_positions(Position::new(0, 0), Direction::UP,
+                                         &[Position::new(0, 1), Position::new(0, 2)]);
+        let food = Food::new(Position::new(9, 9));
+
+        //when
+        let safe_moves = snake.safe_moves(&food, &Board::default());
+
+        //then
+        assert_eq!(safe_moves, [false, false, true], "Only turning right should be safe");
+    }
+
+    #[test]
+    pub fn bounding_box_should_enclose_l_shaped_snake() {
+        //given
+        let snake = snake_from_positions(Position::new(6, 2), Direction::UP,
+                                         &[Position::new(6, 3), Position::new(6, 4), Position::new(5, 4), Position::new(4, 4)]);
+
+        //when
+        let (min, max) = snake.bounding_box();
+
+        //then
+        assert_eq!(min, Position::new(4, 2), "Top left corner should touch the tail and the head");
+        assert_eq!(max, Position::new(6, 4), "Bottom right corner should touch the head column and the bottom row");
+    }
+
+    #[test]
+    pub fn get_distances_should_detect_far_apple_on_diagonal() {
+        //given
+        let snake = snake_from_positions(Position::new(2, 7), Direction::RIGHT, &[Position::new(1, 7)]);
+        let food = Food::new(Position::new(7, 2));
+
+        //when
+        let distances = snake.get_distances(&food);
+
+        //then
+        assert_eq!(distances.top_right.distance_to_apple, 1.0, "Apple 5 cells up the top right diagonal should be seen");
+        assert_eq!(distances.top_left.distance_to_apple, 0.0, "Apple shouldn't be seen on the opposite horizontal side");
+        assert_eq!(distances.bottom_right.distance_to_apple, 0.0, "Apple shouldn't be seen on the opposite vertical side");
+    }
+
+    struct DistanceScenario {
+        name: &'static str,
+        board: [&'static str; 10],
+        expected: Distances
+    }
+
+    // H - head, o - body, A - apple, . - empty
+    fn parse_board(rows: &[&str]) -> (Snake, Food) {
+        let mut head = None;
+        let mut body = Vec::new();
+        let mut food = None;
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                let position = Position::new(x as i16, y as i16);
+
+                match cell {
+                    'H' => head = Some(position),
+                    'o' => body.push(position),
+                    'A' => food = Some(position),
+                    _ => {}
+                }
+            }
+        }
+
+        // The drawing is the whole board, so the walls don't depend on GRID_SIZE
+        let board = Board::new(rows.first().map_or(0, |row| row.chars().count()) as i16, rows.len() as i16);
+        let snake = Snake {board, ..snake_from_positions(head.expect("Board should have a head"), Direction::UP, &body)};
+
+        (snake, Food::new(food.expect("Board should have an apple")))
+    }
+
+    fn distance_scenarios() -> Vec<DistanceScenario> {
+        vec![
+            DistanceScenario {
+                name: "apple on a diagonal",
+                board: [
+                    "..........",
+                    "..........",
+                    ".......A..",
+                    "..........",
+                    "..........",
+                    "..ooH.....",
+                    "..........",
+                    "..........",
+                    "..........",
+                    "..........",
+                ],
+                expected: Distances {
+                    top: (5.0, 0.0, 0.0).into(),
+                    right: (5.0, 0.0, 0.0).into(),
+                    bottom: (4.0, 0.0, 0.0).into(),
+                    left: (4.0, 0.0, 1.0).into(),
+                    top_right: (5.0 * SQRT_2, 1.0, 0.0).into(),
+                    bottom_right: (4.0 * SQRT_2, 0.0, 0.0).into(),
+                    bottom_left: (4.0 * SQRT_2, 0.0, 0.0).into(),
+                    top_left: (4.0 * SQRT_2, 0.0, 0.0).into()
+                }
+            },
+            DistanceScenario {
+                name: "body blocking a cardinal ray",
+                board: [
+                    "..........",
+                    "..........",
+                    ".....o....",
+                    ".....o....",
+                    ".....o....",
+                    "A....H....",
+                    "..........",
+                    "..........",
+                    "..........",
+                    "..........",
+                ],
+                expected: Distances {
+                    top: (5.0, 0.0, 1.0).into(),
+                    right: (4.0, 0.0, 0.0).into(),
+                    bottom: (4.0, 0.0, 0.0).into(),
+                    left: (5.0, 1.0, 0.0).into(),
+                    top_right: (4.0 * SQRT_2, 0.0, 0.0).into(),
+                    bottom_right: (4.0 * SQRT_2, 0.0, 0.0).into(),
+                    bottom_left: (4.0 * SQRT_2, 0.0, 0.0).into(),
+                    top_left: (5.0 * SQRT_2, 0.0, 0.0).into()
+                }
+            },
+            DistanceScenario {
+                name: "apple straight below the head",
+                board: [
+                    "..........",
+                    "....o.....",
+                    "....o.....",
+                    "....H.....",
+                    "..........",
+                    "..........",
+                    "..........",
+                    "..........",
+                    "....A.....",
+                    "..........",
+                ],
+                expected: Distances {
+                    top: (3.0, 0.0, 1.0).into(),
+                    right: (5.0, 0.0, 0.0).into(),
+                    bottom: (6.0, 1.0, 0.0).into(),
+                    left: (4.0, 0.0, 0.0).into(),
+                    top_right: (3.0 * SQRT_2, 0.0, 0.0).into(),
+                    bottom_right: (5.0 * SQRT_2, 0.0, 0.0).into(),
+                    bottom_left: (4.0 * SQRT_2, 0.0, 0.0).into(),
+                    top_left: (3.0 * SQRT_2, 0.0, 0.0).into()
+                }
+            },
+            DistanceScenario {
+                name: "head in a corner",
+                board: [
+                    "Hoo.......",
+                    "..........",
+                    "..........",
+                    "..........",
+                    "..........",
+                    "..........",
+                    "..........",
+                    "..........",
+                    "..........",
+                    ".........A",
+                ],
+                expected: Distances {
+                    top: (0.0, 0.0, 0.0).into(),
+                    right: (9.0, 0.0, 1.0).into(),
+                    bottom: (9.0, 0.0, 0.0).into(),
+                    left: (0.0, 0.0, 0.0).into(),
+                    top_right: (0.0, 0.0, 0.0).into(),
+                    bottom_right: (9.0 * SQRT_2, 1.0, 0.0).into(),
+                    bottom_left: (0.0, 0.0, 0.0).into(),
+                    top_left: (0.0, 0.0, 0.0).into()
+                }
+            }
+        ]
+    }
+
+    #[test]
+    pub fn get_distances_should_match_documented_boards() {
+        distance_scenarios()
+            .into_iter()
+            .for_each(|scenario| {
+                //given
+                let (snake, food) = parse_board(&scenario.board);
+
+                //when
+                let distances = snake.get_distances(&food);
+
+                //then
+                assert_eq!(distances, scenario.expected, "Distances should match for {}", scenario.name);
+            });
+    }
+}
@@ -0,0 +1,154 @@
+use crate::snake::snake_game::{Direction, DistanceInfo, Distances};
+use rand::Rng;
+
+// One of the 8 symmetries of a square board: an optional horizontal mirror followed by clockwise 90° rotations
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Symmetry {
+    rotations: u8,
+    mirrored: bool
+}
+
+impl Symmetry {
+    pub const IDENTITY: Symmetry = Symmetry {rotations: 0, mirrored: false};
+
+    pub fn new(rotations: u8, mirrored: bool) -> Self {
+        Symmetry {rotations: rotations % 4, mirrored}
+    }
+
+    pub fn random<R: Rng>(rng: &mut R) -> Self {
+        Symmetry::new(rng.gen_range(0..4), rng.gen_bool(0.5))
+    }
+
+    pub fn all() -> Vec<Symmetry> {
+        (0..4).flat_map(|rotations| [Symmetry::new(rotations, false), Symmetry::new(rotations, true)])
+            .collect()
+    }
+
+    pub fn inverse(&self) -> Self {
+        if self.mirrored {
+            *self
+        } else {
+            Symmetry::new(4 - self.rotations, false)
+        }
+    }
+
+    pub fn transform_vector(&self, vector: (i16, i16)) -> (i16, i16) {
+        let (mut x, mut y) = if self.mirrored {
+            (-vector.0, vector.1)
+        } else {
+            vector
+        };
+
+        for _ in 0..self.rotations {
+            (x, y) = (-y, x);
+        }
+
+        (x, y)
+    }
+
+    pub fn transform_direction(&self, direction: Direction) -> Direction {
+        let vector = match direction {
+            Direction::UP => (0, -1),
+            Direction::RIGHT => (1, 0),
+            Direction::DOWN => (0, 1),
+            Direction::LEFT => (-1, 0)
+        };
+
+        match self.transform_vector(vector) {
+            (0, -1) => Direction::UP,
+            (1, 0) => Direction::RIGHT,
+            (0, 1) => Direction::DOWN,
+            _ => Direction::LEFT
+        }
+    }
+
+    // Every ray's reading is moved to the ray it's mapped onto
+    pub fn transform_distances(&self, distances: &Distances) -> Distances {
+        let mut transformed = distances.clone();
+
+        for (vector, info) in distances_with_vectors(distances) {
+            *ray_by_vector(&mut transformed, self.transform_vector(vector)) = *info;
+        }
+
+        transformed
+    }
+}
+
+pub fn distances_with_vectors(distances: &Distances) -> [((i16, i16), &DistanceInfo); 8] {
+    [
+        ((0, -1), &distances.top),
+        ((1, 0), &distances.right),
+        ((0, 1), &distances.bottom),
+        ((-1, 0), &distances.left),
+        ((1, -1), &distances.top_right),
+        ((1, 1), &distances.bottom_right),
+        ((-1, 1), &distances.bottom_left),
+        ((-1, -1), &distances.top_left)
+    ]
+}
+
+pub fn ray_by_vector(distances: &mut Distances, vector: (i16, i16)) -> &mut DistanceInfo {
+    match vector {
+        (0, -1) => &mut distances.top,
+        (1, 0) => &mut distances.right,
+        (0, 1) => &mut distances.bottom,
+        (-1, 0) => &mut distances.left,
+        (1, -1) => &mut distances.top_right,
+        (1, 1) => &mut distances.bottom_right,
+        (-1, 1) => &mut distances.bottom_left,
+        _ => &mut distances.top_left
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ai::controller::{Controller, NeuralController};
+    use crate::ai::neural_network::NeuralNetwork;
+    use crate::ai::test_helpers::single_layer_options;
+    use crate::ai::snake_trainer::FIRST_LAYER_SIZE;
+    use crate::snake::snake_game::{Direction, Food, Position, Snake};
+    use crate::snake::symmetry::Symmetry;
+
+    #[test]
+    pub fn inverse_should_undo_every_symmetry() {
+        //given
+        let directions = [Direction::UP, Direction::RIGHT, Direction::DOWN, Direction::LEFT];
+
+        //when-then
+        for symmetry in Symmetry::all() {
+            for direction in directions {
+                assert!(symmetry.inverse().transform_direction(symmetry.transform_direction(direction)) == direction,
+                        "Inverse of {:?} should restore the direction", symmetry);
+            }
+        }
+    }
+
+    #[test]
+    pub fn rotated_sensors_should_give_same_physical_move_for_symmetric_genome() {
+        //given
+        // Every output only looks at the apple flag of its own cardinal ray: top, right, bottom, left
+        let mut weights = vec![0.0; FIRST_LAYER_SIZE * 4];
+        for (output, apple_input) in [1, 4, 7, 10].iter().enumerate() {
+            weights[output * FIRST_LAYER_SIZE + apple_input] = 5.0;
+        }
+
+        let options = single_layer_options();
+
+        let snake = Snake::new(Position::new(5, 5));
+        let food = Food::new(Position::new(5, 2));
+
+        let mut controller = NeuralController::new(
+            NeuralNetwork::new_with_weights(weights.clone(), options.clone()).unwrap());
+        let mut rotated_controller = NeuralController::new(
+            NeuralNetwork::new_with_weights(weights, options).unwrap())
+            .with_symmetry(Symmetry::new(1, false));
+
+        //when
+        let direction = controller.decide(&snake, &food);
+        let rotated_direction = rotated_controller.decide(&snake, &food);
+
+        //then
+        assert!(direction == Direction::UP, "Snake should go towards the apple");
+        assert!(rotated_direction == direction, "Rotation shouldn't change the physical move");
+    }
+}
@@ -4,29 +4,43 @@ use ggez::conf::{WindowMode, WindowSetup};
 use ggez::glam::Vec2;
 use ggez::graphics::{Canvas, Color, Drawable, DrawParam};
 use ggez::input::keyboard::{KeyCode, KeyInput};
-use rand::rngs::ThreadRng;
-use rand::{Rng, thread_rng};
-use crate::snake::snake_game::{Ate, Direction, Food, Position, Snake};
-use crate::visualisation::game_constants::{FPS, GRID_SIZE, SCREEN_SIZE};
+use crate::snake::food_spawner::{FoodSpawner, SeededFoodSpawner};
+use crate::snake::game_simulation::advance_game;
+use crate::snake::snake_game::{Board, Direction, Food, Position, Snake};
+use crate::visualisation::game_constants::{FPS, GRID_CELL_SIZE, GRID_SIZE, MAX_FPS, MIN_FPS, SCREEN_SIZE, SHOW_GRID};
+use crate::visualisation::grid::draw_grid;
+use crate::visualisation::pause::PauseControl;
 
 struct SnakeGameState {
     snake: Snake,
     food: Food,
     game_over: bool,
-    rng: ThreadRng
+    food_spawner: SeededFoodSpawner,
+    score: u32,
+    fps: u32,
+    pause: PauseControl,
+    show_grid: bool
 }
 
 impl SnakeGameState {
     pub fn new() -> Self {
-        let snake_pos: Position = (GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into();
+        SnakeGameState::with_food_spawner(SeededFoodSpawner::from_entropy())
+    }
 
-        let rng = thread_rng();
+    pub fn new_seeded(seed: u64) -> Self {
+        SnakeGameState::with_food_spawner(SeededFoodSpawner::new(seed))
+    }
 
+    fn with_food_spawner(food_spawner: SeededFoodSpawner) -> Self {
         let mut game_state = SnakeGameState {
-            snake: Snake::new(snake_pos),
+            snake: SnakeGameState::new_snake(),
             food: Food::new(Position::new(0, 0)),
             game_over: false,
-            rng
+            food_spawner,
+            score: 0,
+            fps: FPS,
+            pause: PauseControl::default(),
+            show_grid: SHOW_GRID
         };
 
         game_state.food = game_state.generate_new_food();
@@ -34,35 +48,50 @@ impl SnakeGameState {
         game_state
     }
 
-    pub fn generate_new_food(&mut self) -> Food {
-        let mut new_food_position: Position = (
-            self.rng.gen_range(0..GRID_SIZE.0),
-            self.rng.gen_range(0..GRID_SIZE.1)
-        ).into();
-
-        while self.snake.is_in_position(new_food_position) {
-            new_food_position = (
-            self.rng.gen_range(0..GRID_SIZE.0),
-            self.rng.gen_range(0..GRID_SIZE.1)
-            ).into();
+    fn new_snake() -> Snake {
+        Snake::new((GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into())
+    }
+
+    // Fresh snake and food, the settings and the food sequence carry on
+    pub fn restart(&mut self) {
+        self.snake = SnakeGameState::new_snake();
+        self.food = self.generate_new_food();
+        self.score = 0;
+        self.pause = PauseControl::new(self.pause.is_paused());
+        self.game_over = false;
+    }
+
+    // Escape quits and R starts a new game, returns true when the game should quit
+    fn handle_game_over_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Escape => true,
+            KeyCode::R => {
+                self.restart();
+                false
+            },
+            _ => false
         }
+    }
+
+    pub fn generate_new_food(&mut self) -> Food {
+        self.food_spawner.spawn(&self.snake)
+    }
 
-        Food::new(new_food_position)
+    pub fn get_fps(&self) -> u32 {
+        self.fps
+    }
+
+    pub fn set_fps(&mut self, fps: u32) {
+        self.fps = fps.clamp(MIN_FPS, MAX_FPS);
     }
 }
 
 impl EventHandler<GameError> for SnakeGameState {
     fn update(&mut self, ctx: &mut Context) -> Result<(), GameError> {
-        while ctx.time.check_update_time(FPS) {
-            if !self.game_over {
-                self.snake.update_state(&self.food);
-
-                if let Some(ate) = self.snake.get_ate() {
-                    match ate {
-                        Ate::Food => self.food = self.generate_new_food(),
-                        Ate::Itself | Ate::Border => self.game_over = true
-                    }
-                }
+        while ctx.time.check_update_time(self.fps) {
+            if !self.game_over && self.pause.should_advance() {
+                self.game_over = advance_game(&mut self.snake, &mut self.food, &mut self.food_spawner,
+                                              &mut self.score).is_some();
             }
         }
         Ok(())
@@ -72,7 +101,7 @@ impl EventHandler<GameError> for SnakeGameState {
         let mut canvas = Canvas::from_frame(ctx, Color::from_rgb(255, 255, 255));
 
         if self.game_over {
-            let mut text = graphics::Text::new("Game Over!");
+            let mut text = graphics::Text::new(format!("Game Over! Score: {}\nR - restart, Escape - quit", self.score));
             text.set_scale(48.);
 
             let (text_width, text_height) = match text.dimensions(ctx) {
@@ -87,6 +116,10 @@ impl EventHandler<GameError> for SnakeGameState {
                     .color(Color::from_rgb(0, 0, 0))
             )
         } else {
+            if self.show_grid {
+                draw_grid(ctx, &mut canvas, Board::default(), GRID_CELL_SIZE)?;
+            }
+
             self.snake.draw(&mut canvas);
             self.food.draw(&mut canvas);
         }
@@ -103,9 +136,19 @@ impl EventHandler<GameError> for SnakeGameState {
             self.snake.move_in_dir(dir);
         }
 
-        if self.game_over {
-            if input.keycode == Some(KeyCode::Escape) {
-            ctx.request_quit();
+        match input.keycode {
+            Some(KeyCode::Equals | KeyCode::Plus | KeyCode::NumpadAdd) => self.set_fps(self.fps + 1),
+            Some(KeyCode::Minus | KeyCode::NumpadSubtract) => self.set_fps(self.fps.saturating_sub(1)),
+            Some(KeyCode::L) => self.show_grid = !self.show_grid,
+            Some(code) => {
+                self.pause.handle_key(code);
+            }
+            None => {}
+        }
+
+        if let Some(code) = input.keycode.filter(|_| self.game_over) {
+            if self.handle_game_over_key(code) {
+                ctx.request_quit();
             }
         }
 
@@ -124,3 +167,80 @@ pub fn play_game() -> GameResult {
     event::run(ctx, events_loop, state)
 }
 
+#[cfg(test)]
+mod test {
+    use ggez::input::keyboard::KeyCode;
+    use crate::snake::snake_game::Board;
+    use crate::visualisation::game::SnakeGameState;
+    use crate::visualisation::game_constants::{FPS, MAX_FPS, MIN_FPS};
+
+    #[test]
+    pub fn r_should_restart_finished_game() {
+        //given
+        let mut state = SnakeGameState::new_seeded(2024);
+        state.game_over = true;
+        state.score = 5;
+
+        //when
+        let quit = state.handle_game_over_key(KeyCode::R);
+
+        //then
+        assert!(!quit, "Restarting shouldn't quit the game");
+        assert!(!state.game_over, "New game shouldn't be over");
+        assert_eq!(state.score, 0, "Score should start from 0");
+        assert_eq!(state.snake.get_length(), 2, "Snake should start with its initial length");
+        assert!(Board::default().contains(state.snake.get_head_coordinates()), "Snake should be on the board");
+        assert!(Board::default().contains(state.food.get_position()), "Food should be on the board");
+        assert!(!state.snake.is_in_position(state.food.get_position()), "Food shouldn't be placed on the snake");
+        assert!(state.handle_game_over_key(KeyCode::Escape), "Escape should still quit");
+    }
+
+    #[test]
+    pub fn seeded_states_should_generate_same_food_sequence() {
+        //given
+        let mut first_state = SnakeGameState::new_seeded(2024);
+        let mut second_state = SnakeGameState::new_seeded(2024);
+
+        //when-then
+        assert_eq!(first_state.food.get_position(), second_state.food.get_position(),
+                   "First food should be the same");
+
+        for _ in 0..10 {
+            assert_eq!(first_state.generate_new_food().get_position(), second_state.generate_new_food().get_position(),
+                       "Every next food should be the same");
+        }
+    }
+
+    #[test]
+    pub fn restart_should_stay_paused_without_requested_steps() {
+        //given
+        let mut state = SnakeGameState::new_seeded(2024);
+        state.pause.handle_key(KeyCode::Space);
+        state.pause.handle_key(KeyCode::Period);
+
+        //when
+        state.restart();
+
+        //then
+        assert!(state.pause.is_paused(), "Restarted game should stay paused");
+        assert!(!state.pause.should_advance(), "Steps requested before the restart shouldn't move the new snake");
+    }
+
+    #[test]
+    pub fn set_fps_should_clamp_to_sane_range() {
+        //given
+        let mut state = SnakeGameState::new();
+
+        //when-then
+        assert_eq!(state.get_fps(), FPS, "Game should start with default FPS");
+
+        state.set_fps(0);
+        assert_eq!(state.get_fps(), MIN_FPS, "FPS shouldn't go below the minimum");
+
+        state.set_fps(MAX_FPS + 100);
+        assert_eq!(state.get_fps(), MAX_FPS, "FPS shouldn't go above the maximum");
+
+        state.set_fps(15);
+        assert_eq!(state.get_fps(), 15, "FPS inside the range should be kept");
+    }
+}
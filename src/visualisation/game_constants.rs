@@ -1,7 +1,15 @@
 use once_cell::sync::Lazy;
+use crate::snake::snake_game::CellStyle;
 
 pub const GRID_SIZE: (i16, i16) = (10, 10);
-pub const GRID_CELL_SIZE: (i16, i16) = (48, 48);
+
+// The board is scaled to fit this area, only the rendering depends on it, e.g. make it smaller on low resolution screens
+pub const TARGET_GAME_SCREEN_SIZE: (f32, f32) = (480.0, 480.0);
+
+pub const GRID_CELL_SIZE: (f32, f32) = (
+    TARGET_GAME_SCREEN_SIZE.0 / GRID_SIZE.0 as f32,
+    TARGET_GAME_SCREEN_SIZE.1 / GRID_SIZE.1 as f32
+);
 
 pub const MAX_Y_DISTANCE: f64 = GRID_SIZE.1 as f64 - 1.0;
 
@@ -10,8 +18,8 @@ pub const MAX_X_DISTANCE: f64 = GRID_SIZE.0 as f64 - 1.0;
 pub static MAX_DISTANCE: Lazy<f64> = Lazy::new(|| ((GRID_SIZE.0.pow(2) + GRID_SIZE.1.pow(2)) as f64).sqrt());
 
 pub const GAME_SCREEN_SIZE: (f32, f32) = (
-    (GRID_SIZE.0 * GRID_CELL_SIZE.0) as f32,
-    (GRID_SIZE.1 * GRID_CELL_SIZE.1) as f32
+    GRID_SIZE.0 as f32 * GRID_CELL_SIZE.0,
+    GRID_SIZE.1 as f32 * GRID_CELL_SIZE.1
 );
 
 pub const SCREEN_SIZE: (f32, f32) = (
@@ -19,4 +27,26 @@ pub const SCREEN_SIZE: (f32, f32) = (
     GAME_SCREEN_SIZE.1 + 500.0
 );
 
-pub const FPS: u32 = 10;
\ No newline at end of file
+pub const FPS: u32 = 10;
+
+pub const MIN_FPS: u32 = 1;
+
+pub const MAX_FPS: u32 = 60;
+
+pub const MOVE_SMOOTHING_MARGIN: Option<f64> = Some(0.05);
+
+// Replays the previous genome on the same board, drawn translucent behind the current one
+pub const SHOW_GHOST: bool = false;
+
+pub const GHOST_ALPHA: f32 = 0.3;
+
+// Mini-boards shown by `--grid` when no count is given
+pub const GRID_GAMES: usize = 9;
+
+pub const CELL_STYLE: CellStyle = CellStyle::Filled;
+
+// Normalized distance below which a wall or body reading is drawn as dangerous in the stats
+pub const DANGER_THRESHOLD: f64 = 0.2;
+
+// Faint lines between the cells, toggled with L in both games
+pub const SHOW_GRID: bool = false;
\ No newline at end of file
@@ -0,0 +1,54 @@
+use ggez::{Context, GameError};
+use ggez::graphics::{Canvas, Color, DrawParam, Mesh};
+use ggez::mint::Point2;
+use crate::snake::snake_game::Board;
+
+const GRID_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.15);
+const GRID_THICKNESS: f32 = 1.0;
+
+// Lines between the cells, the outer border is left to the games
+pub fn grid_lines(board: Board, cell_size: (f32, f32)) -> Vec<[Point2<f32>; 2]> {
+    let width = board.width as f32 * cell_size.0;
+    let height = board.height as f32 * cell_size.1;
+
+    let vertical_lines = (1..board.width)
+        .map(|x| x as f32 * cell_size.0)
+        .map(|x| [Point2 {x, y: 0.0}, Point2 {x, y: height}]);
+
+    let horizontal_lines = (1..board.height)
+        .map(|y| y as f32 * cell_size.1)
+        .map(|y| [Point2 {x: 0.0, y}, Point2 {x: width, y}]);
+
+    vertical_lines.chain(horizontal_lines).collect()
+}
+
+pub fn draw_grid(ctx: &mut Context, canvas: &mut Canvas, board: Board, cell_size: (f32, f32)) -> Result<(), GameError> {
+    for line in grid_lines(board, cell_size) {
+        let mesh = Mesh::new_line(ctx, &line, GRID_THICKNESS, GRID_COLOR)?;
+
+        canvas.draw(&mesh, DrawParam::default());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use ggez::mint::Point2;
+    use crate::snake::snake_game::Board;
+    use crate::visualisation::grid::grid_lines;
+
+    #[test]
+    pub fn grid_lines_should_separate_every_cell() {
+        //given
+        let board = Board::new(4, 3);
+
+        //when
+        let lines = grid_lines(board, (10.0, 20.0));
+
+        //then
+        assert_eq!(lines.len(), 3 + 2, "There should be a line between every pair of columns and rows");
+        assert_eq!(lines[0], [Point2 {x: 10.0, y: 0.0}, Point2 {x: 10.0, y: 60.0}], "First line should be vertical");
+        assert_eq!(lines[4], [Point2 {x: 0.0, y: 40.0}, Point2 {x: 40.0, y: 40.0}], "Last line should be horizontal");
+    }
+}
@@ -1,114 +1,473 @@
+use std::path::Path;
+use std::sync::mpsc::Receiver;
 use ggez::event::EventHandler;
 use ggez::{Context, ContextBuilder, event, GameError, GameResult, graphics};
 use ggez::conf::{WindowMode, WindowSetup};
 use ggez::glam::Vec2;
-use ggez::graphics::{Canvas, Color, DrawParam, Mesh};
+use ggez::graphics::{Canvas, Color, DrawParam, Mesh, Rect};
 use ggez::input::keyboard::{KeyCode, KeyInput};
 use ggez::mint::Point2;
+use itertools::Itertools;
 use rand::prelude::ThreadRng;
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+use crate::ai::controller::{Controller, NeuralController};
+use crate::ai::genetic_algorithm::Evaluation;
+use crate::ai::genome_io::load_genomes;
 use crate::ai::neural_network::NeuralNetwork;
 use crate::ai::neural_network_utils::NeuralNetworkOptions;
-use crate::ai::snake_trainer::{generate_network_input, generate_new_food, generate_random_position, interpret_network_output};
-use crate::snake::snake_game::{Ate, DistanceInfo, Distances, Food, Snake};
-use crate::visualisation::game_constants::{FPS, GAME_SCREEN_SIZE, MAX_DISTANCE, MAX_X_DISTANCE, MAX_Y_DISTANCE, SCREEN_SIZE};
+use crate::ai::snake_trainer::{generate_random_position, InputSettings};
+use crate::snake::food_spawner::{FoodSpawner, SeededFoodSpawner};
+use crate::snake::game_simulation::advance_game;
+use crate::snake::snake_game::{Board, Direction, DistanceInfo, Distances, Food, Position, Snake};
+use crate::visualisation::game_constants::{DANGER_THRESHOLD, FPS, GAME_SCREEN_SIZE, GHOST_ALPHA, GRID_CELL_SIZE, MAX_DISTANCE, MAX_FPS, MAX_X_DISTANCE,
+                                           MAX_Y_DISTANCE, MIN_FPS, MOVE_SMOOTHING_MARGIN, SCREEN_SIZE, SHOW_GHOST, SHOW_GRID};
+use crate::visualisation::grid::draw_grid;
+use crate::visualisation::pause::PauseControl;
+
+const TEXT_COLOR: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+const DANGER_COLOR: Color = Color::new(0.8, 0.0, 0.0, 1.0);
+const SAFE_COLOR: Color = Color::new(0.0, 0.6, 0.0, 1.0);
+const APPLE_COLOR: Color = Color::new(0.9, 0.5, 0.0, 1.0);
+const ARROW_COLOR: Color = Color::new(1.0, 0.85, 0.0, 1.0);
+const ARROW_THICKNESS: f32 = 3.0;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PlaybackRange {
+    pub start_index: usize,
+    pub count: usize
+}
+
+impl PlaybackRange {
+    pub fn new(start_index: usize, count: usize) -> Self {
+        PlaybackRange {start_index, count}
+    }
+
+    // The last 5% of the generations
+    pub fn last_generations(n_of_weights: usize) -> Self {
+        let start_index = (0.95 * n_of_weights as f64) as usize;
+
+        PlaybackRange::new(start_index, n_of_weights - start_index)
+    }
+}
+
+// Range plays consecutive genomes once, SingleBestLoop replays the genome with the highest training fitness until the window is closed,
+// Grid plays the n fittest genomes at the same time, each on its own mini-board
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PlaybackMode {
+    Range(PlaybackRange),
+    SingleBestLoop,
+    Grid(usize)
+}
+
+// A genome playing its own seeded board, used for the ghost and for the grid of games
+struct SubGame {
+    snake: Snake,
+    food: Food,
+    food_spawner: SeededFoodSpawner,
+    controller: NeuralController,
+    score: u32,
+    game_over: bool
+}
+
+impl SubGame {
+    fn new(controller: NeuralController, seed: u64) -> Self {
+        let (snake, food, food_spawner) = seeded_start(seed);
+
+        SubGame {snake, food, food_spawner, controller, score: 0, game_over: false}
+    }
+
+    // New board for the same genome
+    fn restart(&mut self, seed: u64) {
+        let (snake, food, food_spawner) = seeded_start(seed);
+
+        self.controller.reset_input_history();
+
+        self.snake = snake;
+        self.food = food;
+        self.food_spawner = food_spawner;
+        self.score = 0;
+        self.game_over = false;
+    }
+
+    fn step(&mut self) {
+        if self.game_over {
+            return
+        }
+
+        let move_dir = self.controller.decide(&self.snake, &self.food);
+
+        self.snake.move_in_dir(move_dir);
+
+        self.game_over = advance_game(&mut self.snake, &mut self.food, &mut self.food_spawner, &mut self.score).is_some();
+    }
+
+    fn draw(&self, canvas: &mut Canvas, alpha: f32) {
+        if !self.game_over {
+            self.snake.draw_with_alpha(canvas, alpha);
+            self.food.draw_with_alpha(canvas, alpha);
+        }
+    }
+}
+
+// The demo either plays genomes or watches any other controller, e.g. the oracle, in the same window
+enum DemoController {
+    Neural(Box<NeuralController>),
+    Other(Box<dyn Controller>)
+}
+
+impl DemoController {
+    fn update_weights(&mut self, weights: Vec<f64>) {
+        if let DemoController::Neural(controller) = self {
+            controller.update_weights(weights);
+        }
+    }
+
+    fn get_last_entropy(&self) -> Option<f64> {
+        match self {
+            DemoController::Neural(controller) => controller.get_last_entropy(),
+            DemoController::Other(_) => None
+        }
+    }
+}
+
+impl Controller for DemoController {
+    fn decide(&mut self, snake: &Snake, food: &Food) -> Direction {
+        match self {
+            DemoController::Neural(controller) => controller.decide(snake, food),
+            DemoController::Other(controller) => controller.decide(snake, food)
+        }
+    }
+}
+
+// Highest training fitness wins, without the scores the last genome is taken as the best
+pub(crate) fn best_genome_index(n_of_weights: usize, evaluations: &[Evaluation]) -> usize {
+    evaluations.iter()
+        .take(n_of_weights)
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.fitness.total_cmp(&b.fitness))
+        .map_or(n_of_weights - 1, |(index, _)| index)
+}
+
+// Indices of the `count` genomes with the highest training fitness, best first.
+// Without the scores the last genomes are taken, newest first
+fn best_genome_indices(n_of_weights: usize, evaluations: &[Evaluation], count: usize) -> Vec<usize> {
+    if evaluations.is_empty() {
+        return (0..n_of_weights).rev().take(count).collect()
+    }
+
+    evaluations.iter()
+        .take(n_of_weights)
+        .enumerate()
+        .sorted_by(|(_, a), (_, b)| b.fitness.total_cmp(&a.fitness))
+        .take(count)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+// The same seed always gives the same start position and food sequence
+fn seeded_start(seed: u64) -> (Snake, Food, SeededFoodSpawner) {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let snake = Snake::new(generate_random_position(&mut rng));
+
+    let mut food_spawner = SeededFoodSpawner::new(seed);
+
+    let food = food_spawner.spawn(&snake);
+
+    (snake, food, food_spawner)
+}
+
+fn build_controller(weights: Vec<f64>, neural_network_options: NeuralNetworkOptions, input_settings: InputSettings) -> NeuralController {
+    let neural_network = NeuralNetwork::new_with_weights(weights, neural_network_options).unwrap();
+
+    let mut controller = NeuralController::new(neural_network)
+        .with_entropy_tracking()
+        .with_input_settings(input_settings);
+
+    if let Some(margin) = MOVE_SMOOTHING_MARGIN {
+        controller = controller.with_move_smoothing(margin);
+    }
+
+    controller
+}
 
 struct MLSnakeGameState {
     current_game_index: usize,
+    end_index: usize,
     weights: Vec<Vec<f64>>,
+    // How each genome did in training, empty when the genomes come without scores
+    evaluations: Vec<Evaluation>,
     snake: Snake,
     food: Food,
+    food_spawner: SeededFoodSpawner,
     game_over: bool,
     rng: ThreadRng,
-    neural_network: NeuralNetwork,
-    current_score: u16,
+    controller: DemoController,
+    current_score: u32,
     stop: bool,
-    distances: Distances
+    distances: Distances,
+    receiver: Option<Receiver<(u64, Evaluation, Vec<f64>)>>,
+    // The training result is shown as apples eaten, the fitness is only shown when asked for
+    show_fitness: bool,
+    neural_network_options: Option<NeuralNetworkOptions>,
+    input_settings: InputSettings,
+    show_ghost: bool,
+    ghost: Option<SubGame>,
+    fps: u32,
+    pause: PauseControl,
+    show_grid: bool,
+    loop_single_best: bool,
+    // Where the last move went, turning back is resolved to going forward like in `move_in_dir`
+    last_move: Option<Direction>,
+    // Seed of the current board, the ghost plays the same one
+    board_seed: u64
 }
 
 impl MLSnakeGameState {
-    fn new(neural_network_options: NeuralNetworkOptions, weights: Vec<Vec<f64>>) -> Self {
-        let snake_pos = generate_random_position();
+    fn new(neural_network_options: NeuralNetworkOptions, weights: Vec<Vec<f64>>, playback_range: PlaybackRange,
+           input_settings: InputSettings) -> Self {
+        let current_game_index = playback_range.start_index.min(weights.len() - 1);
+
+        let end_index = (current_game_index + playback_range.count.max(1)).min(weights.len());
+
+        let controller = build_controller(weights[current_game_index].clone(), neural_network_options.clone(), input_settings);
 
-        let rng = thread_rng();
+        MLSnakeGameState::from_parts(DemoController::Neural(Box::new(controller)), weights, current_game_index, end_index,
+                                     Some(neural_network_options), input_settings)
+    }
+
+    // Watches the controller play a new board after every game, there are no genomes or ghosts
+    fn with_controller(controller: Box<dyn Controller>) -> Self {
+        let mut state = MLSnakeGameState::from_parts(DemoController::Other(controller), Vec::new(), 0, 1, None,
+                                                     InputSettings::default());
 
-        let current_game_index = (0.95 * weights.len() as f64) as usize;
+        state.loop_single_best = true;
 
-        let neural_network = NeuralNetwork::new_with_weights(weights[0].clone(), neural_network_options).unwrap();
+        state
+    }
 
-        let snake = Snake::new(snake_pos);
+    fn from_parts(controller: DemoController, weights: Vec<Vec<f64>>, current_game_index: usize, end_index: usize,
+                  neural_network_options: Option<NeuralNetworkOptions>, input_settings: InputSettings) -> Self {
+        let mut rng = thread_rng();
 
-        let food = generate_new_food(&snake);
+        let board_seed = rng.gen();
 
-        let current_score = 0_u16;
+        let (snake, food, food_spawner) = seeded_start(board_seed);
 
         let distances = snake.get_distances(&food);
 
-        MLSnakeGameState {
+        let mut state = MLSnakeGameState {
             snake,
             food,
-            neural_network,
+            food_spawner,
+            controller,
             rng,
             game_over: false,
             current_game_index,
+            end_index,
             weights,
-            current_score,
+            evaluations: Vec::new(),
+            current_score: 0,
             stop: false,
-            distances
+            distances,
+            receiver: None,
+            show_fitness: false,
+            neural_network_options,
+            input_settings,
+            show_ghost: SHOW_GHOST,
+            ghost: None,
+            fps: FPS,
+            pause: PauseControl::default(),
+            show_grid: SHOW_GRID,
+            loop_single_best: false,
+            last_move: None,
+            board_seed
+        };
+
+        state.ghost = state.create_ghost();
+
+        state
+    }
+
+    fn new_single_best_loop(neural_network_options: NeuralNetworkOptions, weights: Vec<Vec<f64>>, evaluations: Vec<Evaluation>,
+                            input_settings: InputSettings) -> Self {
+        let best_index = best_genome_index(weights.len(), &evaluations);
+
+        let mut state = MLSnakeGameState::new(neural_network_options, weights, PlaybackRange::new(best_index, 1),
+                                              input_settings)
+            .with_evaluations(evaluations);
+
+        state.loop_single_best = true;
+
+        state
+    }
+
+    fn with_evaluations(mut self, evaluations: Vec<Evaluation>) -> Self {
+        self.evaluations = evaluations;
+        self
+    }
+
+    fn with_fps(mut self, fps: u32) -> Self {
+        self.set_fps(fps);
+        self
+    }
+
+    fn with_paused(mut self, paused: bool) -> Self {
+        self.pause = PauseControl::new(paused);
+        self
+    }
+
+    fn set_fps(&mut self, fps: u32) {
+        self.fps = fps.clamp(MIN_FPS, MAX_FPS);
+    }
+
+    // Plays every genome from the file, fails if any of them doesn't fit the network.
+    // `input_settings` have to be the ones the genomes were trained with
+    fn from_saved(path: &Path, neural_network_options: NeuralNetworkOptions, input_settings: InputSettings) -> Result<Self, String> {
+        let weights = load_genomes(path)?;
+
+        if weights.is_empty() {
+            return Err(format!("There are no genomes in {}", path.display()))
+        }
+
+        input_settings.check_input_layer(&neural_network_options)?;
+
+        let expected_length = neural_network_options.weight_count();
+
+        if let Some(genome) = weights.iter().find(|genome| genome.len() != expected_length) {
+            return Err(format!("Genome length: {} doesn't match network weight count: {}", genome.len(), expected_length))
         }
+
+        let playback_range = PlaybackRange::new(0, weights.len());
+
+        Ok(MLSnakeGameState::new(neural_network_options, weights, playback_range, input_settings))
     }
-}
 
-impl EventHandler<GameError> for MLSnakeGameState {
-    fn update(&mut self, ctx: &mut Context) -> Result<(), GameError> {
-        while ctx.time.check_update_time(FPS) {
-            if !self.game_over && !self.stop {
+    fn new_live(neural_network_options: NeuralNetworkOptions, receiver: Receiver<(u64, Evaluation, Vec<f64>)>, input_settings: InputSettings) -> Self {
+        let (_, first_evaluation, first_weights) = receiver.recv().unwrap();
 
-                let input = generate_network_input(&self.snake, &self.food);
+        let mut state = MLSnakeGameState::new(neural_network_options, vec![first_weights], PlaybackRange::new(0, 1),
+                                              input_settings)
+            .with_evaluations(vec![first_evaluation]);
 
-                let output = self.neural_network.get_output(input).unwrap();
+        state.receiver = Some(receiver);
 
-                let move_dir = interpret_network_output(&output);
+        state
+    }
 
-                self.snake.move_in_dir(move_dir);
+    fn receive_new_weights(&mut self) {
+        if let Some(receiver) = &self.receiver {
+            for (_, evaluation, weights) in receiver.try_iter() {
+                self.weights.push(weights);
+                self.evaluations.push(evaluation);
+            }
 
-                self.snake.update_state(&self.food);
+            self.current_game_index = self.weights.len() - 1;
+        }
+    }
 
-                self.distances = self.snake.get_distances(&self.food);
+    // Returns false when there are no more genomes to play
+    fn start_next_game(&mut self) -> bool {
+        if self.receiver.is_some() {
+            self.receive_new_weights();
+        } else if self.loop_single_best {
+            // Same genome again, only the board changes
+        } else if self.current_game_index + 1 < self.end_index {
+            self.current_game_index += 1;
+        } else {
+            return false
+        }
 
-                if let Some(ate) = self.snake.get_ate() {
-                    match ate {
-                        Ate::Food => {
-                            self.food = generate_new_food(&self.snake);
-                            self.current_score += 1;
-                        },
-                        Ate::Itself | Ate::Border => self.game_over = true
-                    }
-                }
-            } else {
-                if self.current_game_index < self.weights.len() {
+        self.board_seed = self.rng.gen();
 
-                    let snake_pos = generate_random_position();
+        let (snake, food, food_spawner) = seeded_start(self.board_seed);
 
-                    let snake = Snake::new(snake_pos);
+        if let Some(weights) = self.weights.get(self.current_game_index) {
+            self.controller.update_weights(weights.clone());
+        }
 
-                    let food = generate_new_food(&snake);
+        self.snake = snake;
 
-                    self.neural_network.update_weights(self.weights[self.current_game_index].clone());
+        self.food = food;
 
-                    self.current_game_index += 1;
+        self.food_spawner = food_spawner;
 
-                    self.snake = snake;
+        self.current_score = 0;
 
-                    self.food = food;
+        self.game_over = false;
+        self.stop = false;
+        self.last_move = None;
 
-                    self.current_score = 0;
+        self.ghost = self.create_ghost();
 
-                    self.game_over = false;
-                    self.stop = false;
-                } else {
-                    ctx.request_quit();
-                }
+        true
+    }
+
+    fn create_ghost(&self) -> Option<SubGame> {
+        if !self.show_ghost || self.current_game_index == 0 {
+            return None
+        }
+
+        let neural_network_options = self.neural_network_options.clone()?;
+
+        let previous_weights = self.weights[self.current_game_index - 1].clone();
+
+        let controller = build_controller(previous_weights, neural_network_options, self.input_settings);
+
+        Some(SubGame::new(controller, self.board_seed))
+    }
+
+    // What the current genome did in training, None when the genomes come without scores
+    fn training_result(&self) -> Option<String> {
+        let evaluation = self.evaluations.get(self.current_game_index)?;
+
+        if self.show_fitness {
+            Some(format!("Best fitness: {:.1}", evaluation.fitness))
+        } else {
+            Some(format!("Best: {} apples", evaluation.apples))
+        }
+    }
+
+    // One update of the demo, returns false when there are no more genomes to play
+    fn tick(&mut self) -> bool {
+        if !self.pause.should_advance() {
+            return true
+        }
+
+        if !self.game_over && !self.stop {
+            self.step();
+
+            true
+        } else {
+            self.start_next_game()
+        }
+    }
+
+    // Advances the current game and its ghost by one move
+    fn step(&mut self) {
+        let move_dir = self.controller.decide(&self.snake, &self.food);
+
+        self.last_move = Some(self.snake.direction_for_move(self.snake.move_for_direction(move_dir)));
+
+        self.snake.move_in_dir(move_dir);
+
+        self.game_over = advance_game(&mut self.snake, &mut self.food, &mut self.food_spawner,
+                                      &mut self.current_score).is_some();
+
+        self.distances = self.snake.get_distances(&self.food);
+
+        if let Some(ghost) = &mut self.ghost {
+            ghost.step();
+        }
+    }
+}
+
+impl EventHandler<GameError> for MLSnakeGameState {
+    fn update(&mut self, ctx: &mut Context) -> Result<(), GameError> {
+        while ctx.time.check_update_time(self.fps) {
+            if !self.tick() {
+                ctx.request_quit();
             }
         }
         Ok(())
@@ -117,9 +476,23 @@ impl EventHandler<GameError> for MLSnakeGameState {
     fn draw(&mut self, ctx: &mut Context) -> Result<(), GameError> {
         let mut canvas = Canvas::from_frame(ctx, Color::from_rgb(255, 255, 255));
 
+        if self.show_grid {
+            draw_grid(ctx, &mut canvas, Board::default(), GRID_CELL_SIZE)?;
+        }
+
+        if let Some(ghost) = &self.ghost {
+            ghost.draw(&mut canvas, GHOST_ALPHA);
+        }
+
         self.snake.draw(&mut canvas);
         self.food.draw(&mut canvas);
 
+        if let Some(last_move) = self.last_move {
+            for line in move_arrow(self.snake.get_head_coordinates(), last_move) {
+                canvas.draw(&Mesh::new_line(ctx, &line, ARROW_THICKNESS, ARROW_COLOR)?, DrawParam::default());
+            }
+        }
+
         self.draw_distances(&mut canvas)?;
 
         let mut text = graphics::Text::new(format!("Current gen: {}, current score: {}",
@@ -134,7 +507,7 @@ impl EventHandler<GameError> for MLSnakeGameState {
                 .color(Color::from_rgb(0, 0, 0))
         );
 
-        self.draw_border(ctx, &mut canvas)?;
+        draw_border(ctx, &mut canvas)?;
 
         canvas.finish(ctx)?;
 
@@ -147,7 +520,15 @@ impl EventHandler<GameError> for MLSnakeGameState {
         if let Some(code) = input.keycode {
             match code {
                 KeyCode::Right => self.stop = true,
-                _ => {}
+                KeyCode::G => self.show_ghost = !self.show_ghost,
+                KeyCode::L => self.show_grid = !self.show_grid,
+                KeyCode::F => self.show_fitness = !self.show_fitness,
+                _ if self.pause.handle_key(code) => {}
+                _ => {
+                    if let Some(fps) = adjusted_fps(self.fps, code) {
+                        self.fps = fps;
+                    }
+                }
             }
         };
 
@@ -155,46 +536,46 @@ impl EventHandler<GameError> for MLSnakeGameState {
     }
 }
 
-impl MLSnakeGameState {
-    fn draw_border(&self, ctx: &mut Context, canvas: &mut Canvas) -> Result<(), GameError> {
-        let thickness = 2.0;
-        let color = Color::from_rgb(0, 0, 0);
-        let top_line = Mesh::new_line(
-            ctx,
-            &[Point2::from_slice(&[0.0, 0.0]), [GAME_SCREEN_SIZE.0, 0.0].into()],
-            thickness,
-            color
-        )?;
-
-        let bottom_line = Mesh::new_line(
-            ctx,
-            &[Point2::from_slice(&[0.0, GAME_SCREEN_SIZE.1]), [GAME_SCREEN_SIZE.0, GAME_SCREEN_SIZE.1].into()],
-            thickness,
-            color
-        )?;
-
-        let left_line = Mesh::new_line(
-            ctx,
-            &[Point2::from_slice(&[0.0, 0.0]), [0.0, GAME_SCREEN_SIZE.1].into()],
-            thickness,
-            color
-        )?;
-
-        let right_line = Mesh::new_line(
-            ctx,
-            &[Point2::from_slice(&[GAME_SCREEN_SIZE.0, 0.0]), [GAME_SCREEN_SIZE.0, GAME_SCREEN_SIZE.1].into()],
-            thickness,
-            color
-        )?;
-
-        canvas.draw(&top_line, DrawParam::default());
-        canvas.draw(&left_line, DrawParam::default());
-        canvas.draw(&bottom_line, DrawParam::default());
-        canvas.draw(&right_line, DrawParam::default());
-
-        Ok(())
-    }
+fn draw_border(ctx: &mut Context, canvas: &mut Canvas) -> Result<(), GameError> {
+    let thickness = 2.0;
+    let color = Color::from_rgb(0, 0, 0);
+    let top_line = Mesh::new_line(
+        ctx,
+        &[Point2::from_slice(&[0.0, 0.0]), [GAME_SCREEN_SIZE.0, 0.0].into()],
+        thickness,
+        color
+    )?;
+
+    let bottom_line = Mesh::new_line(
+        ctx,
+        &[Point2::from_slice(&[0.0, GAME_SCREEN_SIZE.1]), [GAME_SCREEN_SIZE.0, GAME_SCREEN_SIZE.1].into()],
+        thickness,
+        color
+    )?;
+
+    let left_line = Mesh::new_line(
+        ctx,
+        &[Point2::from_slice(&[0.0, 0.0]), [0.0, GAME_SCREEN_SIZE.1].into()],
+        thickness,
+        color
+    )?;
+
+    let right_line = Mesh::new_line(
+        ctx,
+        &[Point2::from_slice(&[GAME_SCREEN_SIZE.0, 0.0]), [GAME_SCREEN_SIZE.0, GAME_SCREEN_SIZE.1].into()],
+        thickness,
+        color
+    )?;
+
+    canvas.draw(&top_line, DrawParam::default());
+    canvas.draw(&left_line, DrawParam::default());
+    canvas.draw(&bottom_line, DrawParam::default());
+    canvas.draw(&right_line, DrawParam::default());
+
+    Ok(())
+}
 
+impl MLSnakeGameState {
     fn draw_distances(&self, canvas: &mut Canvas) -> Result<(), GameError> {
         let x = GAME_SCREEN_SIZE.0 + 50.0;
 
@@ -225,20 +606,50 @@ impl MLSnakeGameState {
 
         y += 15.0 + 5.0;
 
-        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.top, "Top", x, MAX_Y_DISTANCE)?;
-        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.top_right, "Top right", x, *MAX_DISTANCE)?;
-        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.right, "Right", x, MAX_X_DISTANCE)?;
-        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.bottom_right, "Bottom right", x, *MAX_DISTANCE)?;
-        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.bottom, "Bottom", x, MAX_Y_DISTANCE)?;
-        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.bottom_left, "Bottom left", x, *MAX_DISTANCE)?;
-        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.left, "Left", x, MAX_X_DISTANCE)?;
-        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.top_left, "Top left", x, *MAX_DISTANCE)?;
+        if let Some(entropy) = self.controller.get_last_entropy() {
+            let mut text = graphics::Text::new(format!("Output entropy: {:.3}", entropy));
+            text.set_scale(15.0);
+
+            canvas.draw(
+                &text,
+                DrawParam::new()
+                    .dest(Vec2::new(x, y))
+                    .color(Color::from_rgb(0, 0, 0))
+            );
+
+            y += 15.0 + 5.0;
+        }
+
+        if let Some(training_result) = self.training_result() {
+            let mut text = graphics::Text::new(training_result);
+            text.set_scale(15.0);
+
+            canvas.draw(
+                &text,
+                DrawParam::new()
+                    .dest(Vec2::new(x, y))
+                    .color(Color::from_rgb(0, 0, 0))
+            );
+
+            y += 15.0 + 5.0;
+        }
+
+        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.top, (0, -1), "Top", x, MAX_Y_DISTANCE)?;
+        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.top_right, (1, -1), "Top right", x, *MAX_DISTANCE)?;
+        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.right, (1, 0), "Right", x, MAX_X_DISTANCE)?;
+        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.bottom_right, (1, 1), "Bottom right", x, *MAX_DISTANCE)?;
+        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.bottom, (0, 1), "Bottom", x, MAX_Y_DISTANCE)?;
+        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.bottom_left, (-1, 1), "Bottom left", x, *MAX_DISTANCE)?;
+        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.left, (-1, 0), "Left", x, MAX_X_DISTANCE)?;
+        self.draw_distance_info(canvas, &mut y, 5.0, &self.distances.top_left, (-1, -1), "Top left", x, *MAX_DISTANCE)?;
 
         Ok(())
     }
 
-    fn draw_distance_info(&self, canvas: &mut Canvas, start_y: &mut f32, space_between: f32, distance: &DistanceInfo, name: &str, x: f32, max: f64) -> Result<(), GameError> {
-        let mut text = graphics::Text::new(format!("{} to wall: {}", name, distance.distance_to_wall / max));
+    fn draw_distance_info(&self, canvas: &mut Canvas, start_y: &mut f32, space_between: f32, distance: &DistanceInfo,
+                          vector: (i16, i16), name: &str, x: f32, max: f64) -> Result<(), GameError> {
+        let normalized_wall_distance = distance.distance_to_wall / max;
+        let mut text = graphics::Text::new(format!("{} to wall: {}", name, normalized_wall_distance));
         let size = 20.0;
         text.set_scale(size);
 
@@ -246,7 +657,7 @@ impl MLSnakeGameState {
             &text,
             DrawParam::new()
                 .dest(Vec2::new(x, *start_y))
-                .color(Color::from_rgb(0, 0, 0))
+                .color(distance_color(normalized_wall_distance, DANGER_THRESHOLD))
         );
 
         text = graphics::Text::new(format!("{} to apple: {}", name, distance.distance_to_apple));
@@ -257,10 +668,13 @@ impl MLSnakeGameState {
             &text,
             DrawParam::new()
                 .dest(Vec2::new(x, *start_y))
-                .color(Color::from_rgb(0, 0, 0))
+                .color(apple_color(distance.distance_to_apple))
         );
 
-        text = graphics::Text::new(format!("{} to body: {}", name, distance.distance_to_body));
+        let body_distance = distance_to_body(&self.snake, vector);
+        let body_reading = body_distance.map_or_else(|| "none".to_string(), |body_distance| (body_distance / max).to_string());
+
+        text = graphics::Text::new(format!("{} to body: {}", name, body_reading));
         text.set_scale(size);
         *start_y += size + space_between;
 
@@ -268,7 +682,7 @@ impl MLSnakeGameState {
             &text,
             DrawParam::new()
                 .dest(Vec2::new(x, *start_y))
-                .color(Color::from_rgb(0, 0, 0))
+                .color(body_color(body_distance, max, DANGER_THRESHOLD))
         );
 
         *start_y += size + 2.0 * space_between;
@@ -277,13 +691,666 @@ impl MLSnakeGameState {
     }
 }
 
-pub fn play_game_with_ml(neural_network_options: NeuralNetworkOptions, weights: Vec<Vec<f64>>) -> GameResult {
+// Several genomes playing at once, every one of them restarts on a new board as soon as it dies
+struct MultiGameState {
+    games: Vec<SubGame>,
+    rng: ThreadRng,
+    fps: u32,
+    pause: PauseControl
+}
+
+impl MultiGameState {
+    // Plays the `count` fittest genomes, the best one in the top left corner
+    fn new(neural_network_options: NeuralNetworkOptions, weights: &[Vec<f64>], evaluations: &[Evaluation], count: usize,
+           input_settings: InputSettings) -> Self {
+        let mut rng = thread_rng();
+
+        let games = best_genome_indices(weights.len(), evaluations, count.max(1)).into_iter()
+            .map(|index| build_controller(weights[index].clone(), neural_network_options.clone(), input_settings))
+            .map(|controller| SubGame::new(controller, rng.gen()))
+            .collect();
+
+        MultiGameState {games, rng, fps: FPS, pause: PauseControl::default()}
+    }
+
+    fn with_fps(mut self, fps: u32) -> Self {
+        self.fps = fps.clamp(MIN_FPS, MAX_FPS);
+        self
+    }
+
+    fn with_paused(mut self, paused: bool) -> Self {
+        self.pause = PauseControl::new(paused);
+        self
+    }
+
+    // Every game moves on its own board, finished games start over
+    fn step(&mut self) {
+        for game in &mut self.games {
+            if game.game_over {
+                game.restart(self.rng.gen());
+            } else {
+                game.step();
+            }
+        }
+    }
+
+    fn columns(&self) -> usize {
+        (self.games.len() as f64).sqrt().ceil() as usize
+    }
+
+    // Screen coordinates that squeeze the whole game area into the index-th cell of the grid
+    fn mini_board_coordinates(&self, index: usize) -> Rect {
+        let columns = self.columns() as f32;
+        let (column, row) = ((index % self.columns()) as f32, (index / self.columns()) as f32);
+
+        Rect::new(-column * GAME_SCREEN_SIZE.0, -row * GAME_SCREEN_SIZE.1, columns * SCREEN_SIZE.0, columns * SCREEN_SIZE.1)
+    }
+}
+
+impl EventHandler<GameError> for MultiGameState {
+    fn update(&mut self, ctx: &mut Context) -> Result<(), GameError> {
+        while ctx.time.check_update_time(self.fps) {
+            if self.pause.should_advance() {
+                self.step();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> Result<(), GameError> {
+        let mut canvas = Canvas::from_frame(ctx, Color::from_rgb(255, 255, 255));
+        let text_scale = 28.0 * self.columns() as f32;
+
+        for (index, game) in self.games.iter().enumerate() {
+            canvas.set_screen_coordinates(self.mini_board_coordinates(index));
+
+            game.draw(&mut canvas, 1.0);
+
+            let mut text = graphics::Text::new(format!("Score: {}", game.score));
+            text.set_scale(text_scale);
+
+            canvas.draw(&text, DrawParam::new().dest(Vec2::new(10.0, 5.0)).color(TEXT_COLOR));
+
+            draw_border(ctx, &mut canvas)?;
+        }
+
+        canvas.set_screen_coordinates(Rect::new(0.0, 0.0, SCREEN_SIZE.0, SCREEN_SIZE.1));
+
+        let mut text = graphics::Text::new(format!("Genomes: {}, best score: {}",
+                                                   self.games.len(), self.games.iter().map(|game| game.score).max().unwrap_or(0)));
+        text.set_scale(28.);
+
+        canvas.draw(
+            &text,
+            DrawParam::new()
+                .dest(Vec2::new(GAME_SCREEN_SIZE.0 + 20.0, 5.0))
+                .color(TEXT_COLOR)
+        );
+
+        canvas.finish(ctx)?;
+
+        ggez::timer::yield_now();
+
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, _ctx: &mut Context, input: KeyInput, _repeated: bool) -> Result<(), GameError> {
+        match input.keycode {
+            Some(code) if self.pause.handle_key(code) => {}
+            Some(code) => {
+                if let Some(fps) = adjusted_fps(self.fps, code) {
+                    self.fps = fps;
+                }
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+}
+
+// + and - change the speed of both demos by one frame per second, within the FPS limits
+fn adjusted_fps(fps: u32, code: KeyCode) -> Option<u32> {
+    match code {
+        KeyCode::Equals | KeyCode::Plus | KeyCode::NumpadAdd => Some((fps + 1).clamp(MIN_FPS, MAX_FPS)),
+        KeyCode::Minus | KeyCode::NumpadSubtract => Some(fps.saturating_sub(1).clamp(MIN_FPS, MAX_FPS)),
+        _ => None
+    }
+}
+
+// Shaft from the middle of the head cell towards `direction`, then the two sides of the tip
+fn move_arrow(head: Position, direction: Direction) -> Vec<[Point2<f32>; 2]> {
+    let mut next = head;
+    next.make_a_move(direction);
+
+    let (dx, dy) = ((next.x - head.x) as f32, (next.y - head.y) as f32);
+    let cell_rect = Rect::from(head);
+    let center = Point2 {x: cell_rect.x + cell_rect.w / 2.0, y: cell_rect.y + cell_rect.h / 2.0};
+    let (length, side) = (0.4 * cell_rect.w.min(cell_rect.h), 0.15 * cell_rect.w.min(cell_rect.h));
+
+    let tip = Point2 {x: center.x + dx * length, y: center.y + dy * length};
+    // Back along the shaft and out to both sides
+    let wing = |sign: f32| Point2 {x: tip.x - dx * side - dy * side * sign, y: tip.y - dy * side + dx * side * sign};
+
+    vec![[center, tip], [wing(1.0), tip], [wing(-1.0), tip]]
+}
+
+// Red below the danger threshold, green otherwise
+pub(crate) fn distance_color(normalized_distance: f64, danger_threshold: f64) -> Color {
+    if normalized_distance < danger_threshold {
+        DANGER_COLOR
+    } else {
+        SAFE_COLOR
+    }
+}
+
+// Free distance between the head and the first body cell along the ray, measured like the distance to the wall.
+// None when the ray reaches the wall without hitting the body.
+fn distance_to_body(snake: &Snake, (dx, dy): (i16, i16)) -> Option<f64> {
+    let board = snake.get_board();
+    let head = snake.get_head_coordinates();
+    let step_length = ((dx * dx + dy * dy) as f64).sqrt();
+
+    let mut position = Position::new(head.x + dx, head.y + dy);
+    let mut free_cells = 0;
+
+    while board.contains(position) {
+        if snake.is_in_position(position) {
+            return Some(free_cells as f64 * step_length)
+        }
+
+        position = Position::new(position.x + dx, position.y + dy);
+        free_cells += 1;
+    }
+
+    None
+}
+
+// Normalised like the wall distance, a ray without any body is safe
+fn body_color(body_distance: Option<f64>, max: f64, danger_threshold: f64) -> Color {
+    match body_distance {
+        Some(body_distance) => distance_color(body_distance / max, danger_threshold),
+        None => SAFE_COLOR
+    }
+}
+
+fn apple_color(apple_reading: f64) -> Color {
+    if apple_reading > 0.0 {
+        APPLE_COLOR
+    } else {
+        TEXT_COLOR
+    }
+}
+
+// Plays the default range of the last generations when `playback_mode` is None
+pub fn play_game_with_ml(neural_network_options: NeuralNetworkOptions, weights: Vec<Vec<f64>>, evaluations: Vec<Evaluation>,
+                         playback_mode: Option<PlaybackMode>, input_settings: InputSettings, fps: u32,
+                         start_paused: bool) -> GameResult {
     let (ctx, events_loop) = ContextBuilder::new("Snake game", "Siemano")
         .window_setup(WindowSetup::default().title("Snake game"))
         .window_mode(WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
         .build()?;
 
-    let state = MLSnakeGameState::new(neural_network_options, weights);
+    let playback_mode = playback_mode.unwrap_or_else(|| PlaybackMode::Range(PlaybackRange::last_generations(weights.len())));
+
+    let state = match playback_mode {
+        PlaybackMode::Range(playback_range) => MLSnakeGameState::new(neural_network_options, weights, playback_range,
+                                                                     input_settings)
+            .with_evaluations(evaluations),
+        PlaybackMode::SingleBestLoop => MLSnakeGameState::new_single_best_loop(neural_network_options, weights, evaluations,
+                                                                               input_settings),
+        PlaybackMode::Grid(count) => {
+            let state = MultiGameState::new(neural_network_options, &weights, &evaluations, count, input_settings)
+                .with_fps(fps)
+                .with_paused(start_paused);
+
+            event::run(ctx, events_loop, state);
+        }
+    };
+
+    let state = state
+        .with_fps(fps)
+        .with_paused(start_paused);
+
+    event::run(ctx, events_loop, state);
+}
+
+// Opens the demo for genomes saved with `save_genomes`, without training
+pub fn play_saved(path: &Path, neural_network_options: NeuralNetworkOptions, input_settings: InputSettings) -> GameResult {
+    let state = MLSnakeGameState::from_saved(path, neural_network_options, input_settings).map_err(GameError::CustomError)?;
+
+    let (ctx, events_loop) = ContextBuilder::new("Snake game", "Siemano")
+        .window_setup(WindowSetup::default().title("Snake game"))
+        .window_mode(WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
 
     event::run(ctx, events_loop, state);
-}
\ No newline at end of file
+}
+
+// Same window as for the genomes, but the moves come from `controller`
+pub fn play_game_with_controller(controller: Box<dyn Controller>, fps: u32) -> GameResult {
+    let (ctx, events_loop) = ContextBuilder::new("Snake game", "Siemano")
+        .window_setup(WindowSetup::default().title("Snake game"))
+        .window_mode(WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+
+    let state = MLSnakeGameState::with_controller(controller).with_fps(fps);
+
+    event::run(ctx, events_loop, state);
+}
+
+pub fn play_game_with_ml_live(neural_network_options: NeuralNetworkOptions, receiver: Receiver<(u64, Evaluation, Vec<f64>)>,
+                              input_settings: InputSettings) -> GameResult {
+    let (ctx, events_loop) = ContextBuilder::new("Snake game", "Siemano")
+        .window_setup(WindowSetup::default().title("Snake game"))
+        .window_mode(WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+
+    let state = MLSnakeGameState::new_live(neural_network_options, receiver, input_settings);
+
+    event::run(ctx, events_loop, state);
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs};
+    use crate::ai::controller::OracleController;
+    use crate::ai::genetic_algorithm::Evaluation;
+    use crate::ai::genome_io::save_genomes;
+    use crate::ai::test_helpers::single_layer_options;
+    use crate::ai::snake_trainer::{InputSettings, FIRST_LAYER_SIZE};
+    use crate::visualisation::game_constants::{DANGER_THRESHOLD, FPS, GRID_SIZE, MAX_FPS, MAX_X_DISTANCE, MAX_Y_DISTANCE, MIN_FPS};
+    use ggez::input::keyboard::KeyCode;
+    use crate::visualisation::ml_game::{adjusted_fps, apple_color, best_genome_indices, body_color, distance_color, distance_to_body, move_arrow, seeded_start, MLSnakeGameState, MultiGameState,
+                                        PlaybackRange, APPLE_COLOR, DANGER_COLOR, SAFE_COLOR, TEXT_COLOR};
+    use crate::snake::snake_game::{Board, Direction, Position, Snake};
+
+    fn small_state(n_of_weights: usize, playback_range: PlaybackRange) -> MLSnakeGameState {
+        let options = single_layer_options();
+        let weights = (0..n_of_weights)
+            .map(|i| vec![i as f64 / n_of_weights as f64; FIRST_LAYER_SIZE * 4])
+            .collect();
+
+        MLSnakeGameState::new(options, weights, playback_range, InputSettings::default())
+    }
+
+    #[test]
+    pub fn from_saved_should_load_every_genome() {
+        //given
+        let options = single_layer_options();
+        let input_settings = InputSettings {standardize: true, ..InputSettings::default()};
+        let genomes = vec![vec![0.5; FIRST_LAYER_SIZE * 4]; 3];
+        let path = env::temp_dir().join(format!("snake_saved_{}.json", std::process::id()));
+        save_genomes(&genomes, &path).unwrap();
+
+        //when
+        let state = MLSnakeGameState::from_saved(&path, options, input_settings);
+        fs::remove_file(&path).unwrap();
+
+        //then
+        let state = state.unwrap_or_else(|error| panic!("State should be created: {}", error));
+
+        assert_eq!(state.input_settings, input_settings, "Genomes should be played with the training input settings");
+        assert_eq!(state.weights.len(), 3, "Every saved genome should be loaded");
+        assert_eq!((state.current_game_index, state.end_index), (0, 3), "Every genome should be played");
+    }
+
+    #[test]
+    pub fn from_saved_should_reject_genome_of_wrong_length() {
+        //given
+        let options = single_layer_options();
+        let path = env::temp_dir().join(format!("snake_saved_wrong_{}.json", std::process::id()));
+        save_genomes(&[vec![0.5; 3]], &path).unwrap();
+
+        //when
+        let state = MLSnakeGameState::from_saved(&path, options, InputSettings::default());
+        fs::remove_file(&path).unwrap();
+
+        //then
+        assert!(state.is_err(), "Genome that doesn't fit the network should be rejected");
+    }
+
+    #[test]
+    pub fn from_saved_should_reject_input_settings_that_dont_fit_the_network() {
+        //given
+        let options = single_layer_options();
+        let input_settings = InputSettings {history_frames: 2, ..InputSettings::default()};
+        let path = env::temp_dir().join(format!("snake_saved_history_{}.json", std::process::id()));
+        save_genomes(&[vec![0.5; FIRST_LAYER_SIZE * 4]], &path).unwrap();
+
+        //when
+        let state = MLSnakeGameState::from_saved(&path, options, input_settings);
+        fs::remove_file(&path).unwrap();
+
+        //then
+        assert!(state.is_err(), "Network input should match the input frames of the settings");
+    }
+
+    #[test]
+    pub fn state_should_play_count_genomes_from_start_index() {
+        //given
+        let mut state = small_state(10, PlaybackRange::new(3, 4));
+
+        //when
+        let mut played = vec![state.current_game_index];
+
+        while state.start_next_game() {
+            played.push(state.current_game_index);
+        }
+
+        //then
+        assert_eq!(played, vec![3, 4, 5, 6], "Exactly 4 genomes starting from index 3 should be played");
+    }
+
+    #[test]
+    pub fn single_best_loop_should_keep_replaying_last_genome_without_evaluations() {
+        //given
+        let options = single_layer_options();
+        let weights = (0..5).map(|i| vec![i as f64 / 5.0; FIRST_LAYER_SIZE * 4]).collect();
+        let mut state = MLSnakeGameState::new_single_best_loop(options, weights, Vec::new(), InputSettings::default());
+
+        //when
+        let played: Vec<_> = (0..10)
+            .map(|_| (state.start_next_game(), state.current_game_index))
+            .collect();
+
+        //then
+        assert_eq!(state.current_game_index, 4, "Last genome should be played");
+        assert!(played.iter().all(|game| *game == (true, 4)), "The same genome should be replayed without ending");
+    }
+
+    #[test]
+    pub fn single_best_loop_should_replay_genome_with_highest_fitness() {
+        //given
+        let options = single_layer_options();
+        let weights = (0..5).map(|i| vec![i as f64 / 5.0; FIRST_LAYER_SIZE * 4]).collect();
+        let evaluations = [3.0, 9.0, 4.0, 7.0, 5.0].into_iter()
+            .map(Evaluation::from)
+            .collect();
+        let mut state = MLSnakeGameState::new_single_best_loop(options, weights, evaluations, InputSettings::default());
+
+        //when
+        let played: Vec<_> = (0..5)
+            .map(|_| (state.start_next_game(), state.current_game_index))
+            .collect();
+
+        //then
+        assert!(played.iter().all(|game| *game == (true, 1)), "Genome with the highest fitness should be replayed, not the last one");
+    }
+
+    #[test]
+    pub fn training_result_should_show_apples_until_fitness_is_toggled() {
+        //given
+        let evaluations = (0..3).map(|i| Evaluation {fitness: 1000.0 * i as f64, apples: 10 + i, ..Default::default()}).collect();
+        let mut state = small_state(3, PlaybackRange::new(1, 2)).with_evaluations(evaluations);
+
+        //when
+        let apples = state.training_result();
+        state.show_fitness = true;
+        let fitness = state.training_result();
+
+        //then
+        assert_eq!(apples.as_deref(), Some("Best: 11 apples"), "Apples of the shown genome should be displayed by default");
+        assert_eq!(fitness.as_deref(), Some("Best fitness: 1000.0"), "Fitness should be displayed after the toggle");
+    }
+
+    #[test]
+    pub fn training_result_should_be_hidden_without_evaluations() {
+        //given
+        let state = small_state(3, PlaybackRange::new(0, 3));
+
+        //when-then
+        assert_eq!(state.training_result(), None, "Genomes without scores should show no training result");
+    }
+
+    #[test]
+    pub fn state_with_oracle_controller_should_move_every_tick() {
+        //given
+        let mut state = MLSnakeGameState::with_controller(Box::new(OracleController));
+
+        //when-then
+        for _ in 0..20 {
+            let head_before = state.snake.get_head_coordinates();
+
+            state.step();
+
+            assert_ne!(state.snake.get_head_coordinates(), head_before, "Oracle should move the snake on every tick");
+            assert!(!state.game_over, "Oracle shouldn't crash this early");
+        }
+
+        assert!(state.start_next_game(), "Oracle should keep playing new boards");
+    }
+
+    #[test]
+    pub fn ghost_should_advance_in_lockstep_with_same_genome() {
+        //given
+        let options = single_layer_options();
+        let genome: Vec<f64> = (0..FIRST_LAYER_SIZE * 4).map(|i| (i as f64).sin()).collect();
+        let mut state = MLSnakeGameState::new(options, vec![genome; 2], PlaybackRange::new(0, 2), InputSettings::default());
+        state.show_ghost = true;
+
+        //when
+        state.start_next_game();
+
+        //then
+        assert!(state.ghost.is_some(), "Second genome should have a ghost of the first one");
+
+        for _ in 0..200 {
+            state.step();
+
+            let ghost = state.ghost.as_ref().unwrap();
+
+            assert_eq!(ghost.snake.get_head_coordinates(), state.snake.get_head_coordinates(), "Ghost head should follow the same path");
+            assert_eq!(ghost.food.get_position(), state.food.get_position(), "Ghost food should come from the same seed");
+            assert_eq!((ghost.score, ghost.game_over), (state.current_score, state.game_over), "Both games should be in the same state");
+
+            if state.game_over {
+                break
+            }
+        }
+    }
+
+    #[test]
+    pub fn first_shown_genome_should_have_ghost_on_same_board() {
+        //given
+        let options = single_layer_options();
+        let genome: Vec<f64> = (0..FIRST_LAYER_SIZE * 4).map(|i| (i as f64).cos()).collect();
+
+        let mut state = MLSnakeGameState::new(options, vec![genome; 2], PlaybackRange::new(1, 1), InputSettings::default());
+        state.show_ghost = true;
+
+        //when
+        state.ghost = state.create_ghost();
+
+        //then
+        assert!(state.ghost.is_some(), "First shown genome should have a ghost of the genome before it");
+
+        for _ in 0..50 {
+            state.step();
+
+            let ghost = state.ghost.as_ref().unwrap();
+
+            assert_eq!(ghost.snake.get_head_coordinates(), state.snake.get_head_coordinates(), "Ghost should start on the same board");
+            assert_eq!(ghost.food.get_position(), state.food.get_position(), "Ghost food should come from the same seed");
+
+            if state.game_over {
+                break
+            }
+        }
+    }
+
+    #[test]
+    pub fn first_genome_should_have_no_ghost() {
+        //given
+        let mut state = small_state(3, PlaybackRange::new(0, 3));
+        state.show_ghost = true;
+
+        //when
+        state.ghost = state.create_ghost();
+
+        //then
+        assert!(state.ghost.is_none(), "There is no previous genome to replay");
+    }
+
+    #[test]
+    pub fn best_genome_indices_should_pick_fittest_genomes_best_first() {
+        //given
+        let evaluations: Vec<Evaluation> = [300.0, 100.0, 500.0, 200.0, 400.0].into_iter().map(Evaluation::from).collect();
+
+        //when-then
+        assert_eq!(best_genome_indices(5, &evaluations, 3), vec![2, 4, 0], "Three fittest genomes should be played, best first");
+        assert_eq!(best_genome_indices(5, &evaluations, 9), vec![2, 4, 0, 3, 1], "Every genome should be played when there are fewer than asked");
+        assert_eq!(best_genome_indices(5, &[], 3), vec![4, 3, 2], "Without scores the last genomes should be played");
+    }
+
+    #[test]
+    pub fn multi_game_state_should_advance_every_game_independently() {
+        //given
+        let options = single_layer_options();
+        let weights: Vec<Vec<f64>> = (0..6)
+            .map(|i| (0..FIRST_LAYER_SIZE * 4).map(|j| ((i * j) as f64).sin()).collect())
+            .collect();
+        let mut state = MultiGameState::new(options, &weights, &[], 4, InputSettings::default());
+        state.games[1].game_over = true;
+        state.games[1].score = 3;
+
+        let heads_before: Vec<_> = state.games.iter().map(|game| game.snake.get_head_coordinates()).collect();
+
+        //when
+        state.step();
+
+        //then
+        assert_eq!(state.games.len(), 4, "Last 4 genomes should be played");
+        assert_eq!(state.columns(), 2, "4 games should fit in a 2x2 grid");
+        assert_eq!((state.games[1].score, state.games[1].game_over), (0, false), "Finished game should start over");
+
+        for index in [0, 2, 3] {
+            let head = state.games[index].snake.get_head_coordinates();
+            let moved = (head.x - heads_before[index].x).abs() + (head.y - heads_before[index].y).abs();
+
+            assert_eq!(moved, 1, "Game {} should move by one cell on its own board", index);
+        }
+    }
+
+    #[test]
+    pub fn move_arrow_should_point_along_chosen_move() {
+        //given
+        // Only the UP output neuron has weights, so the network always picks UP
+        let options = single_layer_options();
+        let genome: Vec<f64> = (0..FIRST_LAYER_SIZE * 4).map(|i| if i < FIRST_LAYER_SIZE { 1.0 } else { 0.0 }).collect();
+        let mut state = MLSnakeGameState::new(options, vec![genome], PlaybackRange::new(0, 1), InputSettings::default());
+
+        //when
+        state.step();
+
+        //then
+        assert!(state.last_move == Some(Direction::UP), "Snake starts facing right, so turning left should go up");
+
+        let arrow = move_arrow(state.snake.get_head_coordinates(), Direction::UP);
+        let [tail, tip] = arrow[0];
+
+        assert_eq!(tail.x, tip.x, "Arrow for UP should be vertical");
+        assert!(tip.y < tail.y, "Arrow for UP should point up the screen");
+        assert!(arrow[1..].iter().all(|line| line[1] == tip), "Both sides of the arrowhead should meet at the tip");
+    }
+
+    #[test]
+    pub fn state_should_start_with_given_fps_and_paused_flag() {
+        //when
+        let default_state = small_state(2, PlaybackRange::new(0, 2));
+        let state = small_state(2, PlaybackRange::new(0, 2)).with_fps(25).with_paused(true);
+        let fast_state = small_state(2, PlaybackRange::new(0, 2)).with_fps(MAX_FPS + 10);
+
+        //then
+        assert_eq!((default_state.fps, default_state.pause.is_paused()), (FPS, false), "Demo should run at default FPS by default");
+        assert_eq!((state.fps, state.pause.is_paused()), (25, true), "Demo should start with the given FPS and paused");
+        assert_eq!(fast_state.fps, MAX_FPS, "FPS shouldn't go above the maximum");
+    }
+
+    #[test]
+    pub fn paused_demo_should_move_only_on_requested_step() {
+        //given
+        let mut state = small_state(2, PlaybackRange::new(0, 2)).with_paused(true);
+        let head_before = state.snake.get_head_coordinates();
+
+        //when
+        state.tick();
+        let head_while_paused = state.snake.get_head_coordinates();
+
+        state.pause.handle_key(KeyCode::Period);
+        state.tick();
+        let head_after_step = state.snake.get_head_coordinates();
+
+        //then
+        assert_eq!(head_while_paused, head_before, "Paused demo shouldn't move on its own");
+        assert_eq!((head_after_step.x - head_before.x).abs() + (head_after_step.y - head_before.y).abs(), 1,
+                   "Requested step should move the snake by one cell");
+    }
+
+    #[test]
+    pub fn seeded_start_should_keep_whole_snake_on_board() {
+        //given
+        let board = Board::default();
+
+        //when-then
+        for seed in 0..200 {
+            let (snake, food, _) = seeded_start(seed);
+
+            assert!(snake.get_positions().iter().all(|position| board.contains(*position)),
+                    "Snake from seed {} should start with its body on the board", seed);
+            assert_eq!(seeded_start(seed).1.get_position(), food.get_position(), "Same seed should give the same food");
+        }
+    }
+
+    #[test]
+    pub fn adjusted_fps_should_change_speed_within_limits() {
+        //when-then
+        assert_eq!(adjusted_fps(FPS, KeyCode::Plus), Some(FPS + 1), "Plus should speed the demo up");
+        assert_eq!(adjusted_fps(FPS, KeyCode::NumpadSubtract), Some(FPS - 1), "Minus should slow the demo down");
+        assert_eq!(adjusted_fps(MAX_FPS, KeyCode::Equals), Some(MAX_FPS), "FPS shouldn't go above the maximum");
+        assert_eq!(adjusted_fps(MIN_FPS, KeyCode::Minus), Some(MIN_FPS), "FPS shouldn't go below the minimum");
+        assert_eq!(adjusted_fps(FPS, KeyCode::G), None, "Other keys shouldn't change the speed");
+    }
+
+    #[test]
+    pub fn last_generations_should_cover_last_five_percent() {
+        //when
+        let playback_range = PlaybackRange::last_generations(2000);
+
+        //then
+        assert_eq!(playback_range, PlaybackRange::new(1900, 100), "Last 100 generations should be played");
+    }
+
+    #[test]
+    pub fn distance_color_should_flag_danger_below_threshold() {
+        //when-then
+        assert_eq!(distance_color(DANGER_THRESHOLD / 2.0, DANGER_THRESHOLD), DANGER_COLOR, "Close wall should be dangerous");
+        assert_eq!(distance_color(DANGER_THRESHOLD, DANGER_THRESHOLD), SAFE_COLOR, "Wall at the threshold should be safe");
+        assert_eq!(body_color(Some(0.0), MAX_X_DISTANCE, DANGER_THRESHOLD), DANGER_COLOR, "Adjacent body should be dangerous");
+        assert_eq!(body_color(None, MAX_X_DISTANCE, DANGER_THRESHOLD), SAFE_COLOR, "Ray without body should be safe");
+        assert_eq!(apple_color(1.0), APPLE_COLOR, "Detected apple should be highlighted");
+        assert_eq!(apple_color(0.0), TEXT_COLOR, "Missing apple should use the default color");
+    }
+
+    #[test]
+    pub fn body_color_should_be_safe_when_body_is_far_along_ray() {
+        //given
+        // Head in the top left corner, the body goes down, along the second row and back up at the far right
+        let body = (0..GRID_SIZE.0).map(|x| Position::new(x, 1))
+            .chain(std::iter::once(Position::new(GRID_SIZE.0 - 1, 0)))
+            .collect::<Vec<_>>();
+        let snake = Snake::from_positions(Position::new(0, 0), &body);
+
+        //when
+        let right = distance_to_body(&snake, (1, 0));
+        let bottom = distance_to_body(&snake, (0, 1));
+        let left = distance_to_body(&snake, (-1, 0));
+
+        //then
+        assert_eq!(right, Some((GRID_SIZE.0 - 2) as f64), "Every cell but the last one should be free on the right");
+        assert_eq!(body_color(right, MAX_X_DISTANCE, DANGER_THRESHOLD), SAFE_COLOR, "Far away body should be safe");
+        assert_eq!(body_color(bottom, MAX_Y_DISTANCE, DANGER_THRESHOLD), DANGER_COLOR, "Adjacent body should be dangerous");
+        assert_eq!(left, None, "Ray into the wall shouldn't see any body");
+    }
+}
@@ -1,3 +1,6 @@
 mod game;
 pub mod ml_game;
+pub mod replay;
+pub mod grid;
+pub(crate) mod pause;
 pub(crate) mod game_constants;
\ No newline at end of file
@@ -0,0 +1,98 @@
+use ggez::input::keyboard::KeyCode;
+
+// Same bindings in every game: Space pauses and resumes, '.' moves one tick while paused
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct PauseControl {
+    paused: bool,
+    pending_steps: u32
+}
+
+impl PauseControl {
+    pub fn new(paused: bool) -> Self {
+        PauseControl {paused, pending_steps: 0}
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // While paused the game only moves one tick per step request
+    pub fn toggle(&mut self) {
+        self.paused = !self.paused;
+        self.pending_steps = 0;
+    }
+
+    pub fn request_step(&mut self) {
+        if self.paused {
+            self.pending_steps += 1;
+        }
+    }
+
+    // Asked once per tick, uses up one requested step when paused
+    pub fn should_advance(&mut self) -> bool {
+        if !self.paused {
+            return true
+        }
+
+        if self.pending_steps > 0 {
+            self.pending_steps -= 1;
+            return true
+        }
+
+        false
+    }
+
+    // Returns false for keys that aren't pause or step
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Space => self.toggle(),
+            KeyCode::Period => self.request_step(),
+            _ => return false
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ggez::input::keyboard::KeyCode;
+    use crate::visualisation::pause::PauseControl;
+
+    #[test]
+    pub fn paused_control_should_advance_only_on_requested_steps() {
+        //given
+        let mut pause = PauseControl::default();
+
+        //when-then
+        assert!(pause.should_advance(), "Running game should advance on every tick");
+
+        pause.request_step();
+        pause.toggle();
+        assert!(!pause.should_advance(), "Paused game shouldn't advance on its own");
+
+        pause.request_step();
+        pause.request_step();
+        assert!(pause.should_advance(), "First requested step should advance");
+        assert!(pause.should_advance(), "Second requested step should advance");
+        assert!(!pause.should_advance(), "Game should freeze again after the requested steps");
+
+        pause.request_step();
+        pause.toggle();
+        assert!(pause.should_advance(), "Resumed game should advance on every tick");
+        assert_eq!(pause.pending_steps, 0, "Steps requested while paused shouldn't be kept after resuming");
+    }
+
+    #[test]
+    pub fn handle_key_should_pause_with_space_and_step_with_period() {
+        //given
+        let mut pause = PauseControl::default();
+
+        //when-then
+        assert!(pause.handle_key(KeyCode::Space) && pause.is_paused(), "Space should pause");
+        assert!(pause.handle_key(KeyCode::Period) && pause.should_advance(), "Period should move one tick");
+        assert!(!pause.should_advance(), "Only one tick should be moved");
+        assert!(!pause.handle_key(KeyCode::P), "P shouldn't be a pause key");
+        assert!(pause.handle_key(KeyCode::Space) && !pause.is_paused(), "Space should resume");
+    }
+}
@@ -0,0 +1,208 @@
+use std::fs;
+use std::path::Path;
+use ggez::event::EventHandler;
+use ggez::{Context, ContextBuilder, event, GameError, GameResult, graphics};
+use ggez::conf::{WindowMode, WindowSetup};
+use ggez::glam::Vec2;
+use ggez::graphics::{Canvas, Color, DrawParam};
+use crate::ai::controller::Controller;
+use crate::snake::game_simulation::GameSimulation;
+use crate::snake::snake_game::{Food, Position, Snake};
+use crate::visualisation::game_constants::{FPS, GAME_SCREEN_SIZE, SCREEN_SIZE};
+
+// Everything needed to draw a single tick of a game, the snake's cells go from the head to the tail
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameFrame {
+    pub snake: Vec<Position>,
+    pub food: Position,
+    pub score: u32
+}
+
+impl GameFrame {
+    pub fn capture(simulation: &GameSimulation) -> Self {
+        GameFrame {
+            snake: simulation.get_snake().get_positions(),
+            food: simulation.get_food().get_position(),
+            score: simulation.get_score()
+        }
+    }
+
+    // score food_x,food_y head_x,head_y body_x,body_y ...
+    fn to_line(&self) -> String {
+        std::iter::once(self.score.to_string())
+            .chain(std::iter::once(&self.food).chain(self.snake.iter())
+                .map(|position| format!("{},{}", position.x, position.y)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn from_line(line: &str) -> Result<Self, String> {
+        let mut fields = line.split_whitespace();
+
+        let score = fields.next()
+            .ok_or_else(|| "Frame is empty".to_string())?
+            .parse::<u32>()
+            .map_err(|error| format!("Couldn't parse the score in \"{}\": {}", line, error))?;
+
+        let positions = fields.map(parse_position).collect::<Result<Vec<_>, _>>()?;
+
+        match positions.split_first() {
+            Some((food, snake)) if !snake.is_empty() => Ok(GameFrame {snake: snake.to_vec(), food: *food, score}),
+            _ => Err(format!("Frame \"{}\" should have the food and the snake", line))
+        }
+    }
+}
+
+fn parse_position(field: &str) -> Result<Position, String> {
+    let (x, y) = field.split_once(',')
+        .ok_or_else(|| format!("Position \"{}\" should be x,y", field))?;
+
+    let parse = |value: &str| value.parse::<i16>()
+        .map_err(|error| format!("Couldn't parse the position \"{}\": {}", field, error));
+
+    Ok(Position::new(parse(x)?, parse(y)?))
+}
+
+// Plays the game to the end, the first frame is the starting board
+pub fn record_game<C: Controller>(controller: &mut C, mut simulation: GameSimulation) -> Vec<GameFrame> {
+    let mut frames = vec![GameFrame::capture(&simulation)];
+
+    while simulation.get_end_reason().is_none() {
+        let direction = controller.decide(simulation.get_snake(), simulation.get_food());
+
+        simulation.step(direction);
+        frames.push(GameFrame::capture(&simulation));
+    }
+
+    frames
+}
+
+// One frame per line
+pub fn save_replay(frames: &[GameFrame], path: &Path) -> Result<(), String> {
+    let content = frames.iter()
+        .map(GameFrame::to_line)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    fs::write(path, content)
+        .map_err(|error| format!("Couldn't write the replay to {}: {}", path.display(), error))
+}
+
+pub fn load_replay(path: &Path) -> Result<Vec<GameFrame>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|error| format!("Couldn't read the replay from {}: {}", path.display(), error))?;
+
+    content.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(GameFrame::from_line)
+        .collect()
+}
+
+struct ReplayState {
+    frames: Vec<GameFrame>,
+    frame_index: usize
+}
+
+impl ReplayState {
+    fn new(frames: Vec<GameFrame>) -> Result<Self, String> {
+        if frames.is_empty() {
+            return Err("Replay doesn't have any frames".to_string())
+        }
+
+        Ok(ReplayState {frames, frame_index: 0})
+    }
+
+    fn current_frame(&self) -> &GameFrame {
+        &self.frames[self.frame_index]
+    }
+}
+
+impl EventHandler<GameError> for ReplayState {
+    fn update(&mut self, ctx: &mut Context) -> Result<(), GameError> {
+        while ctx.time.check_update_time(FPS) {
+            self.frame_index = (self.frame_index + 1).min(self.frames.len() - 1);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> Result<(), GameError> {
+        let mut canvas = Canvas::from_frame(ctx, Color::from_rgb(255, 255, 255));
+        let frame = self.current_frame();
+
+        Snake::from_positions(frame.snake[0], &frame.snake[1..]).draw(&mut canvas);
+        Food::new(frame.food).draw(&mut canvas);
+
+        let mut text = graphics::Text::new(format!("Frame: {}/{}, score: {}",
+                                                   self.frame_index + 1, self.frames.len(), frame.score));
+        text.set_scale(28.);
+
+        canvas.draw(
+            &text,
+            DrawParam::new()
+                .dest(Vec2::new(GAME_SCREEN_SIZE.0 + 20.0, 5.0))
+                .color(Color::from_rgb(0, 0, 0))
+        );
+
+        canvas.finish(ctx)?;
+
+        ggez::timer::yield_now();
+
+        Ok(())
+    }
+}
+
+// Renders a replay saved with `save_replay`, no network is needed
+pub fn play_replay(path: &Path) -> GameResult {
+    let state = load_replay(path)
+        .and_then(ReplayState::new)
+        .map_err(GameError::CustomError)?;
+
+    let (ctx, events_loop) = ContextBuilder::new("Snake game", "Siemano")
+        .window_setup(WindowSetup::default().title("Snake replay"))
+        .window_mode(WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+
+    event::run(ctx, events_loop, state);
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs};
+    use crate::ai::controller::Controller;
+    use crate::snake::food_spawner::SeededFoodSpawner;
+    use crate::snake::game_simulation::GameSimulation;
+    use crate::snake::snake_game::{Direction, Food, Position, Snake};
+    use crate::visualisation::replay::{load_replay, record_game, save_replay, ReplayState};
+
+    struct UpController;
+
+    impl Controller for UpController {
+        fn decide(&mut self, _snake: &Snake, _food: &Food) -> Direction {
+            Direction::UP
+        }
+    }
+
+    #[test]
+    pub fn load_replay_should_return_recorded_frames() {
+        //given
+        let simulation = GameSimulation::with_food_spawner(Snake::new(Position::new(5, 3)), Food::new(Position::new(5, 1)),
+                                                           Box::new(SeededFoodSpawner::new(2024)));
+        let frames = record_game(&mut UpController, simulation);
+        let path = env::temp_dir().join(format!("snake_replay_{}.txt", std::process::id()));
+
+        //when
+        save_replay(&frames, &path).unwrap();
+        let loaded = load_replay(&path);
+        fs::remove_file(&path).unwrap();
+
+        //then
+        assert_eq!(frames.len(), 5, "Snake should hit the wall after four steps");
+        assert_eq!(frames[0].snake, vec![Position::new(5, 3), Position::new(4, 3)], "First frame should be the start");
+        assert_eq!(frames[2].score, 1, "Apple should be eaten on the second step");
+        assert_eq!(loaded, Ok(frames.clone()), "Loaded frames should be the same as recorded");
+
+        let state = ReplayState::new(loaded.unwrap()).unwrap();
+        assert_eq!(state.current_frame(), &frames[0], "Player should start on the first frame");
+    }
+}